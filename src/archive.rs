@@ -0,0 +1,250 @@
+use flate2::read::GzDecoder;
+use simple_error::SimpleError;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use crate::shared::*;
+use crate::timeline::is_timeline_database;
+
+macro_rules! map_err(($result:expr) => ($result.map_err(|e| SimpleError::new(format!("{}", e)))));
+
+#[derive(Debug, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+fn archive_kind(p: &Path) -> Option<ArchiveKind> {
+    let name = p.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else {
+        None
+    }
+}
+
+/// Returns true for the collection archives sidr can read databases from (.zip, .tar, .tar.gz/.tgz).
+pub fn is_archive(p: &Path) -> bool {
+    archive_kind(p).is_some()
+}
+
+/// A database extracted from a collection archive.
+pub struct ExtractedDb {
+    /// Location of the extracted copy.
+    pub path: PathBuf,
+    /// `<archive path>/<entry path>`, recorded in every row of the reports produced from it.
+    pub source: String,
+}
+
+/// The databases extracted from a collection archive.
+#[derive(Default)]
+pub struct ExtractedArchive {
+    /// Windows.edb, Windows.db and S-1-*.db
+    pub databases: Vec<ExtractedDb>,
+    /// Windows Timeline databases (ActivitiesCache.db), joined with the Activity History of
+    /// the search databases of the same archive.
+    pub timelines: Vec<ExtractedDb>,
+}
+
+// Velociraptor collections percent-encode the drive letter and special characters
+// (e.g. uploads/auto/C%3A/ProgramData/...), decode them so the entry is readable.
+fn decode_entry_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Keep only normal components, so a crafted entry can't escape the extraction directory,
+// and drop drive letter colons, which are not valid in Windows file names.
+fn sanitize_entry_path(name: &str) -> PathBuf {
+    Path::new(&decode_entry_name(&name.replace('\\', "/")))
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy().replace(':', "")),
+            _ => None,
+        })
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// Files read along with the databases: the gather database, the SecStore, and the
+// write-ahead log of a Timeline database.
+fn is_companion_file(p: &Path) -> bool {
+    let is_gather = p
+        .file_name()
+        .and_then(|s| s.to_str())
+        .map(|name| name.to_ascii_lowercase())
+        .is_some_and(|name| {
+            name.ends_with("-gather.db")
+                || name == "activitiescache.db-wal"
+                || name == "activitiescache.db-shm"
+        });
+    let in_secstore = p
+        .parent()
+        .and_then(|d| d.file_name())
+        .and_then(|s| s.to_str())
+        .is_some_and(|name| name.eq_ignore_ascii_case("SecStore"));
+    is_gather || in_secstore
+}
+
+fn extract_entry(
+    reader: &mut dyn Read,
+    archive: &Path,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<ExtractedDb, SimpleError> {
+    let path = dest_dir.join(sanitize_entry_path(entry_name));
+    if let Some(parent) = path.parent() {
+        map_err!(fs::create_dir_all(parent))?;
+    }
+    let mut out = map_err!(File::create(&path))?;
+    map_err!(io::copy(reader, &mut out))?;
+    Ok(ExtractedDb {
+        path,
+        source: format!(
+            "{}/{}",
+            archive.to_string_lossy(),
+            decode_entry_name(entry_name)
+        ),
+    })
+}
+
+// Extracts an entry if it is one of the databases or of their companion files.
+fn extract_if_needed(
+    reader: &mut dyn Read,
+    archive: &Path,
+    entry_name: &str,
+    dest_dir: &Path,
+    extracted: &mut ExtractedArchive,
+) -> Result<(), SimpleError> {
+    let path = sanitize_entry_path(entry_name);
+    if is_valid_file(&path) {
+        extracted
+            .databases
+            .push(extract_entry(reader, archive, entry_name, dest_dir)?);
+    } else if is_timeline_database(&path) {
+        extracted
+            .timelines
+            .push(extract_entry(reader, archive, entry_name, dest_dir)?);
+    } else if is_companion_file(&path) {
+        extract_entry(reader, archive, entry_name, dest_dir)?;
+    }
+    Ok(())
+}
+
+fn extract_from_zip(archive: &Path, dest_dir: &Path) -> Result<ExtractedArchive, SimpleError> {
+    let f = map_err!(File::open(archive))?;
+    let mut zip = map_err!(zip::ZipArchive::new(f))?;
+    let mut extracted = ExtractedArchive::default();
+    for i in 0..zip.len() {
+        let mut entry = map_err!(zip.by_index(i))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        extract_if_needed(&mut entry, archive, &name, dest_dir, &mut extracted)?;
+    }
+    Ok(extracted)
+}
+
+fn extract_from_tar<R: Read>(
+    reader: R,
+    archive: &Path,
+    dest_dir: &Path,
+) -> Result<ExtractedArchive, SimpleError> {
+    let mut tar = tar::Archive::new(reader);
+    let mut extracted = ExtractedArchive::default();
+    for entry in map_err!(tar.entries())? {
+        let mut entry = map_err!(entry)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = map_err!(entry.path())?.to_string_lossy().into_owned();
+        extract_if_needed(&mut entry, archive, &name, dest_dir, &mut extracted)?;
+    }
+    Ok(extracted)
+}
+
+/// Extracts every Windows.edb, Windows.db and S-1-*.db found in `archive` into `dest_dir`,
+/// preserving the entry paths, along with the gather databases and SecStore files read with
+/// them and the Windows Timeline databases. Other entries are not written: in a zip they are
+/// skipped without being decompressed, a .tar.gz is one gzip stream and is inflated in full.
+pub fn extract_databases(archive: &Path, dest_dir: &Path) -> Result<ExtractedArchive, SimpleError> {
+    match archive_kind(archive) {
+        Some(ArchiveKind::Zip) => extract_from_zip(archive, dest_dir),
+        Some(ArchiveKind::Tar) => {
+            extract_from_tar(map_err!(File::open(archive))?, archive, dest_dir)
+        }
+        Some(ArchiveKind::TarGz) => extract_from_tar(
+            GzDecoder::new(map_err!(File::open(archive))?),
+            archive,
+            dest_dir,
+        ),
+        None => Err(SimpleError::new(format!(
+            "Unsupported archive type: {}",
+            archive.to_string_lossy()
+        ))),
+    }
+}
+
+#[test]
+fn test_sanitize_entry_path() {
+    assert_eq!(
+        sanitize_entry_path("uploads/auto/C%3A/ProgramData/Microsoft/Search/Data/Applications/Windows/Windows.edb"),
+        PathBuf::from("uploads/auto/C/ProgramData/Microsoft/Search/Data/Applications/Windows/Windows.edb")
+    );
+    assert_eq!(
+        decode_entry_name("uploads/auto/C%3A/ProgramData/Windows.edb"),
+        "uploads/auto/C:/ProgramData/Windows.edb"
+    );
+    assert_eq!(
+        sanitize_entry_path("../../etc/Windows.db"),
+        PathBuf::from("etc/Windows.db")
+    );
+    assert_eq!(
+        sanitize_entry_path("C\\ProgramData\\Windows.db"),
+        PathBuf::from("C/ProgramData/Windows.db")
+    );
+}
+
+#[test]
+fn test_archive_kind() {
+    assert_eq!(archive_kind(Path::new("kape.zip")), Some(ArchiveKind::Zip));
+    assert_eq!(archive_kind(Path::new("cylr.TAR")), Some(ArchiveKind::Tar));
+    assert_eq!(archive_kind(Path::new("c.tar.gz")), Some(ArchiveKind::TarGz));
+    assert_eq!(archive_kind(Path::new("c.tgz")), Some(ArchiveKind::TarGz));
+    assert_eq!(archive_kind(Path::new("Windows.edb")), None);
+}
+
+#[test]
+fn test_is_companion_file() {
+    assert!(is_companion_file(Path::new(
+        "C/ProgramData/Microsoft/Search/Data/Applications/Windows/Windows-gather.db"
+    )));
+    assert!(is_companion_file(Path::new(
+        "C/ProgramData/Microsoft/Search/Data/Applications/Windows/Projects/SystemIndex/SecStore/0001.sdb"
+    )));
+    assert!(is_companion_file(Path::new(
+        "C/Users/u/AppData/Local/ConnectedDevicesPlatform/L.u/ActivitiesCache.db-wal"
+    )));
+    assert!(!is_companion_file(Path::new("C/ProgramData/Windows.db")));
+}
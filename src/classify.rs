@@ -0,0 +1,442 @@
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use simple_error::SimpleError;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::properties::*;
+
+// Routes records to reports, the same way for Windows.edb and Windows.db.
+// The rules format is documented in default_rules.yaml.
+
+const DEFAULT_RULES: &str = include_str!("default_rules.yaml");
+
+/// Category of the records that match no rule.
+pub const FILE_REPORT: &str = "File";
+pub const INTERNET_HISTORY: &str = "Internet_History";
+pub const ACTIVITY_HISTORY: &str = "Activity_History";
+pub const EMAIL: &str = "Email";
+pub const CONTACTS: &str = "Contacts";
+pub const CALENDAR: &str = "Calendar";
+pub const TASKS: &str = "Tasks";
+/// Written in addition to the File report, see `is_document`.
+pub const DOCUMENT_METADATA: &str = "Document_Metadata";
+/// Written in addition to the File report, see `is_media`.
+pub const MEDIA: &str = "Media";
+/// Written in addition to the File report, see `is_cloud_file`.
+pub const CLOUD_FILES: &str = "Cloud_Files";
+/// Written in addition to the File report, see `is_shortcut`.
+pub const SHORTCUTS: &str = "Shortcuts";
+/// Written in addition to the other reports when a $MFT is given, see `write_mft_comparison`.
+pub const MFT_COMPARISON: &str = "MFT_Comparison";
+/// Written when the database is done, see `is_application`.
+pub const APPLICATIONS: &str = "Applications";
+
+/// Reports created for every database, even if no record goes to them.
+pub const DEFAULT_CATEGORIES: [&str; 3] = [FILE_REPORT, INTERNET_HISTORY, ACTIVITY_HISTORY];
+
+/// Layout of the reports whose columns are the same for Windows.edb and Windows.db.
+pub fn report_layout(category: &str) -> Option<&'static ReportLayout> {
+    match category {
+        ACTIVITY_HISTORY => Some(&ACTIVITY_REPORT),
+        EMAIL => Some(&EMAIL_REPORT),
+        CONTACTS => Some(&CONTACTS_REPORT),
+        CALENDAR => Some(&CALENDAR_REPORT),
+        TASKS => Some(&TASKS_REPORT),
+        DOCUMENT_METADATA => Some(&DOCUMENT_REPORT),
+        MEDIA => Some(&MEDIA_REPORT),
+        CLOUD_FILES => Some(&CLOUD_REPORT),
+        SHORTCUTS => Some(&SHORTCUT_REPORT),
+        MFT_COMPARISON => Some(&MFT_REPORT),
+        APPLICATIONS => Some(&APPLICATION_REPORT),
+        _ => None,
+    }
+}
+
+/// Every property of those reports, for the ESE path to select their columns.
+pub fn all_report_properties() -> impl Iterator<Item = &'static Property> {
+    [
+        &ACTIVITY_REPORT,
+        &EMAIL_REPORT,
+        &CONTACTS_REPORT,
+        &CALENDAR_REPORT,
+        &TASKS_REPORT,
+        &DOCUMENT_REPORT,
+        &MEDIA_REPORT,
+        &CLOUD_REPORT,
+        &SHORTCUT_REPORT,
+        &MFT_REPORT,
+        &APPLICATION_REPORT,
+    ]
+    .into_iter()
+    .flat_map(|l| l.properties)
+}
+
+/// Files with authorship metadata, also written to the Document Metadata report.
+pub fn is_document(rec: &dyn PropertyRecord) -> bool {
+    [
+        "System.Author",
+        "System.Document.LastAuthor",
+        "System.Document.RevisionNumber",
+        "System.Document.DateSaved",
+    ]
+    .iter()
+    .any(|p| rec.get_str(p).is_some())
+}
+
+/// Photos, videos and music with camera, location or media metadata, also written to the
+/// Media report.
+pub fn is_media(rec: &dyn PropertyRecord) -> bool {
+    [
+        "System.Photo.DateTaken",
+        "System.Photo.CameraModel",
+        "System.GPS.Latitude",
+        "System.GPS.LatitudeDecimal",
+        "System.Media.Duration",
+    ]
+    .iter()
+    .any(|p| rec.get_bytes(p).is_some())
+}
+
+/// Files synced by a storage provider, local or placeholders, also written to the
+/// Cloud Files report.
+pub fn is_cloud_file(rec: &dyn PropertyRecord) -> bool {
+    [
+        "System.StorageProviderId",
+        "System.StorageProviderFileRemoteUri",
+    ]
+    .iter()
+    .any(|p| rec.get_bytes(p).is_some())
+}
+
+/// .url and .lnk files, also written to the Shortcuts report.
+pub fn is_shortcut(rec: &dyn PropertyRecord) -> bool {
+    let item_type = rec
+        .get_str("System.ItemType")
+        .or_else(|| {
+            let path = rec.get_str("System.ItemPathDisplay")?;
+            path.rfind('.').map(|i| path[i..].to_string())
+        })
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    item_type == ".url" || item_type == ".lnk"
+}
+
+/// Start menu entries and packaged apps, listed in the Applications report.
+pub fn is_application(rec: &dyn PropertyRecord) -> bool {
+    [
+        "System.AppUserModel.ID",
+        "System.Software.ProductName",
+    ]
+    .iter()
+    .any(|p| rec.get_bytes(p).is_some())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConditionCfg {
+    equals: Option<String>,
+    starts_with: Option<String>,
+    not_starts_with: Option<String>,
+    ends_with: Option<String>,
+    contains: Option<String>,
+    regex: Option<String>,
+    exists: Option<bool>,
+    #[serde(default)]
+    ignore_case: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CategoryCfg {
+    name: String,
+    rules: Vec<BTreeMap<String /*property*/, ConditionCfg>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesCfg {
+    categories: Vec<CategoryCfg>,
+}
+
+struct Condition {
+    // property name with dots, as in Windows.db
+    property: String,
+    equals: Option<String>,
+    starts_with: Option<String>,
+    not_starts_with: Option<String>,
+    ends_with: Option<String>,
+    contains: Option<String>,
+    regex: Option<Regex>,
+    exists: Option<bool>,
+    ignore_case: bool,
+}
+
+struct Category {
+    name: String,
+    // any rule matches, all conditions of a rule match
+    rules: Vec<Vec<Condition>>,
+}
+
+pub struct Classifier {
+    categories: Vec<Category>,
+}
+
+impl Condition {
+    fn new(property: &str, cfg: ConditionCfg) -> Result<Self, SimpleError> {
+        let fold = |s: Option<String>| {
+            if cfg.ignore_case {
+                s.map(|s| s.to_lowercase())
+            } else {
+                s
+            }
+        };
+        let regex = match &cfg.regex {
+            Some(r) => Some(
+                RegexBuilder::new(r)
+                    .case_insensitive(cfg.ignore_case)
+                    .build()
+                    .map_err(|e| SimpleError::new(format!("Invalid regex for {property}: {e}")))?,
+            ),
+            None => None,
+        };
+        Ok(Condition {
+            property: property.replace('_', "."),
+            equals: fold(cfg.equals),
+            starts_with: fold(cfg.starts_with),
+            not_starts_with: fold(cfg.not_starts_with),
+            ends_with: fold(cfg.ends_with),
+            contains: fold(cfg.contains),
+            regex,
+            exists: cfg.exists,
+            ignore_case: cfg.ignore_case,
+        })
+    }
+
+    fn matches(&self, value: Option<&str>) -> bool {
+        let value = match (value, self.exists) {
+            (None, Some(false)) => return true,
+            (None, _) | (Some(_), Some(false)) => return false,
+            (Some(v), _) => v,
+        };
+        let folded;
+        let v = if self.ignore_case {
+            folded = value.to_lowercase();
+            folded.as_str()
+        } else {
+            value
+        };
+        self.equals.iter().all(|s| v == s)
+            && self.starts_with.iter().all(|s| v.starts_with(s.as_str()))
+            && !self.not_starts_with.iter().any(|s| v.starts_with(s.as_str()))
+            && self.ends_with.iter().all(|s| v.ends_with(s.as_str()))
+            && self.contains.iter().all(|s| v.contains(s.as_str()))
+            && self.regex.iter().all(|r| r.is_match(value))
+    }
+}
+
+fn parse_rules(yaml: &str) -> Result<Vec<Category>, SimpleError> {
+    let cfg: RulesCfg = serde_yaml::from_str(yaml)
+        .map_err(|e| SimpleError::new(format!("Invalid classification rules: {e}")))?;
+    let mut categories = Vec::new();
+    for c in cfg.categories {
+        if c.name.is_empty() || c.name.eq_ignore_ascii_case(FILE_REPORT) {
+            return Err(SimpleError::new(format!(
+                "Invalid category name '{}'",
+                c.name
+            )));
+        }
+        let mut rules = Vec::new();
+        for rule in c.rules {
+            let conditions = rule
+                .into_iter()
+                .map(|(property, cfg)| Condition::new(&property, cfg))
+                .collect::<Result<Vec<_>, _>>()?;
+            rules.push(conditions);
+        }
+        categories.push(Category {
+            name: c.name,
+            rules,
+        });
+    }
+    Ok(categories)
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        Classifier {
+            categories: parse_rules(DEFAULT_RULES).expect("default_rules.yaml is valid"),
+        }
+    }
+}
+
+impl Classifier {
+    /// Adds user rules: new categories are checked before the existing ones,
+    /// rules of an existing category are added to it.
+    pub fn add_rules(&mut self, yaml: &str) -> Result<(), SimpleError> {
+        let mut new_categories = Vec::new();
+        for mut c in parse_rules(yaml)? {
+            match self.categories.iter_mut().find(|i| i.name == c.name) {
+                Some(existing) => {
+                    c.rules.append(&mut existing.rules);
+                    existing.rules = c.rules;
+                }
+                None => new_categories.push(c),
+            }
+        }
+        new_categories.append(&mut self.categories);
+        self.categories = new_categories;
+        Ok(())
+    }
+
+    pub fn add_rules_file(&mut self, path: &Path) -> Result<(), SimpleError> {
+        let yaml = std::fs::read_to_string(path).map_err(|e| {
+            SimpleError::new(format!("Can't read rules '{}': {e}", path.to_string_lossy()))
+        })?;
+        self.add_rules(&yaml)
+    }
+
+    /// Names of all properties the rules look at, with dots (System.ItemUrl).
+    pub fn properties(&self) -> Vec<&str> {
+        let mut props: Vec<&str> = self
+            .categories
+            .iter()
+            .flat_map(|c| c.rules.iter().flatten())
+            .map(|cond| cond.property.as_str())
+            .collect();
+        props.sort_unstable();
+        props.dedup();
+        props
+    }
+
+    /// Category of a record, `property` returns the string value of a property by its name
+    /// (System.ItemUrl).
+    pub fn classify(&self, property: &dyn Fn(&str) -> Option<String>) -> &str {
+        for c in &self.categories {
+            let matched = c.rules.iter().any(|rule| {
+                rule.iter()
+                    .all(|cond| cond.matches(property(&cond.property).as_deref()))
+            });
+            if matched {
+                return &c.name;
+            }
+        }
+        FILE_REPORT
+    }
+}
+
+#[cfg(test)]
+fn classify_map(c: &Classifier, record: &[(&str, &str)]) -> String {
+    let record: BTreeMap<String, String> = record
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    c.classify(&|p| record.get(p).cloned()).to_string()
+}
+
+#[test]
+fn test_default_rules() {
+    let c = Classifier::default();
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "iehistory://{S-1-5-21-1-2-3-1001}/https://example.com/")]),
+        INTERNET_HISTORY
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "winrt://{S-1-5-21-1-2-3-1001}/LS/Desktop/Microsoft Edge/stable/Default/History/9")]),
+        INTERNET_HISTORY
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "winrt://{S-1-5-21-1-2-3-1001}/LS/Desktop/Microsoft Edge/Beta/Profile 1/History/2")]),
+        INTERNET_HISTORY
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "winrt://{S-1-5-21-1-2-3-1001}/LS/Desktop/Google Chrome/stable/Default/History/1")]),
+        INTERNET_HISTORY
+    );
+    // the list of profiles is not history
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "winrt://{S-1-5-21-1-2-3-1001}/LS/Desktop/Microsoft Edge/stable/Profiles/Default")]),
+        FILE_REPORT
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemType", "ActivityHistoryItem"), ("System.ItemUrl", "winrt://{S-1-5-21-1-2-3-1001}/LS/Desktop/ActivityData/ActivityHistory/{2C7DDE0F-0C21-4B60-9B44-5C3D1DFD4C18}")]),
+        ACTIVITY_HISTORY
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "mapi16://{S-1-5-21-1-2-3-1001}/user@example.com($1a2b3c4d)/0/Inbox/")]),
+        EMAIL
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "mapi16://{S-1-5-21-1-2-3-1001}/user@example.com($1a2b3c4d)/0/Contacts/"), ("System.Kind", "contact")]),
+        CONTACTS
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "mapi16://{S-1-5-21-1-2-3-1001}/user@example.com($1a2b3c4d)/0/Calendar/"), ("System.Message.MessageClass", "IPM.Appointment")]),
+        CALENDAR
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "mapi://{S-1-5-21-1-2-3-1001}/Outlook/0/Tasks/"), ("System.Kind", "task; note")]),
+        TASKS
+    );
+    // People and Calendar app items
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "windows.contacts://{S-1-5-21-1-2-3-1001}/Contacts/2.1"), ("System.Kind", "contact")]),
+        CONTACTS
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "windows.calendar://{S-1-5-21-1-2-3-1001}/Appointments/7"), ("System.Kind", "calendar")]),
+        CALENDAR
+    );
+    // contact and calendar files on disk stay in File
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "file:C:/Users/u/Contacts/alice.vcf"), ("System.Kind", "contact")]),
+        FILE_REPORT
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "FILE:D:/Backup/meeting.ics"), ("System.Kind", "calendar")]),
+        FILE_REPORT
+    );
+    // shortcuts point to the web, but are files
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "file:C:/Users/u/Favorites/Bing.url"), ("System.Link.TargetUrl", "http://www.bing.com/")]),
+        FILE_REPORT
+    );
+    assert_eq!(classify_map(&c, &[]), FILE_REPORT);
+    assert!(c.properties().contains(&"System.ItemUrl"));
+}
+
+#[test]
+fn test_user_rules() {
+    let mut c = Classifier::default();
+    c.add_rules(
+        r#"
+categories:
+  - name: Email
+    rules:
+      - System_ItemUrl: { starts_with: "MAPI://", ignore_case: true }
+  - name: Activity_History
+    rules:
+      - System.ItemUrl: { regex: '^winrt://\{S-1-5-21-[0-9-]+\}/LS/Desktop/ActivityData/' }
+        System.ItemType: { exists: false }
+"#,
+    )
+    .unwrap();
+    assert_eq!(classify_map(&c, &[("System.ItemUrl", "mapi://{S-1-5-21-1}/Inbox/1")]), "Email");
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "winrt://{S-1-5-21-1-1001}/LS/Desktop/ActivityData/ActivityHistory/{1}")]),
+        ACTIVITY_HISTORY
+    );
+    assert_eq!(
+        classify_map(&c, &[("System.ItemUrl", "winrt://{S-1-5-21-1-1001}/LS/Desktop/ActivityData/ActivityHistory/{1}"), ("System.ItemType", ".txt")]),
+        FILE_REPORT
+    );
+    assert!(c.add_rules("categories:\n  - name: File\n    rules: []\n").is_err());
+    assert!(c.add_rules("categories:\n  - name: X\n    rules:\n      - System.ItemUrl: { prefix: a }\n").is_err());
+
+    let mut c = Classifier::default();
+    c.add_rules("categories:\n  - name: Outlook_2010\n    rules:\n      - System.ItemUrl: { starts_with: mapi, not_starts_with: mapi16 }\n")
+        .unwrap();
+    assert_eq!(classify_map(&c, &[("System.ItemUrl", "mapi://{S-1-5-21-1}/Inbox/1")]), "Outlook_2010");
+    assert_eq!(classify_map(&c, &[("System.ItemUrl", "mapi16://{S-1-5-21-1}/Inbox/1")]), EMAIL);
+
+}
@@ -0,0 +1,308 @@
+// Compressed ESE column values.
+//
+// ESE compresses large text and binary values of columns created with compression enabled
+// (System_Search_AutoSummary and other long values of Windows.edb). The high 5 bits of the
+// first byte identify the scheme:
+//   1 - 7-bit ASCII, characters packed into 7 bits each
+//   2 - 7-bit Unicode, UTF-16 code units below 0x80 packed into 7 bits each
+//   3 - Xpress (plain LZ77 of [MS-XCA]), after a 2 byte uncompressed size
+//   5, 6 - Xpress9 and Xpress10, not supported
+// For the 7-bit schemes the low 3 bits hold the number of bits used in the last byte, minus 1.
+//
+// Plain UTF-16 text may start with a byte that looks like one of these schemes (0x0A-0x1F:
+// most CJK and Hangul characters), so only the columns known to be compressed are
+// decompressed first; other values are decompressed only when they are not UTF-16.
+
+use crate::utils::from_utf16;
+
+const COMPRESS_7BITASCII: u8 = 1;
+const COMPRESS_7BITUNICODE: u8 = 2;
+const COMPRESS_XPRESS: u8 = 3;
+
+// Columns of Windows.edb whose large values are stored compressed.
+const COMPRESSED_COLUMNS: [&str; 1] = ["System_Search_AutoSummary"];
+
+/// Text of an ESE column value: UTF-16, or decompressed when it is not.
+/// Values that are neither are written as hex.
+pub fn ese_text(val: &[u8]) -> String {
+    plain_text(val)
+        .or_else(|| decompress_text(val))
+        .unwrap_or_else(|| to_hex(val))
+}
+
+/// Text of a value of a column stored compressed: decompressed first,
+/// as a compressed value may also decode as UTF-16.
+pub fn ese_compressed_text(val: &[u8]) -> String {
+    decompress_text(val)
+        .or_else(|| plain_text(val))
+        .unwrap_or_else(|| to_hex(val))
+}
+
+/// Text of a value of the given column (System_Search_AutoSummary or System.Search.AutoSummary).
+pub fn ese_column_text(column: &str, val: &[u8]) -> String {
+    if COMPRESSED_COLUMNS.contains(&column.replace('.', "_").as_str()) {
+        ese_compressed_text(val)
+    } else {
+        ese_text(val)
+    }
+}
+
+/// Values of a multi-valued ESE text value, separated by a null character.
+pub fn ese_text_list(val: &[u8]) -> Vec<String> {
+    ese_text(val)
+        .split('\0')
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn plain_text(val: &[u8]) -> Option<String> {
+    let s = from_utf16(val);
+    if val.len().is_multiple_of(2) && !s.contains(char::REPLACEMENT_CHARACTER) {
+        Some(s)
+    } else {
+        None
+    }
+}
+
+fn decompress_text(val: &[u8]) -> Option<String> {
+    let s = match val.first()? >> 3 {
+        COMPRESS_7BITASCII => decompress_7bit(val)?
+            .into_iter()
+            .map(|c| c as u8 as char)
+            .collect(),
+        COMPRESS_7BITUNICODE => String::from_utf16(&decompress_7bit(val)?).ok()?,
+        COMPRESS_XPRESS => {
+            let data = decompress_xpress(val)?;
+            if !data.len().is_multiple_of(2) {
+                return None;
+            }
+            from_utf16(&data)
+        }
+        _ => return None,
+    };
+    // a plain UTF-16 value starting with a control character may look like a compressed one
+    if is_text(&s) {
+        Some(s)
+    } else {
+        None
+    }
+}
+
+fn is_text(s: &str) -> bool {
+    s.trim_end_matches('\0')
+        .chars()
+        .all(|c| c != char::REPLACEMENT_CHARACTER && (!c.is_control() || c.is_whitespace()))
+}
+
+fn decompress_7bit(val: &[u8]) -> Option<Vec<u16>> {
+    if val.len() < 2 {
+        return None;
+    }
+    let bits_in_last_byte = (val[0] & 0x07) as usize + 1;
+    let total_bits = (val.len() - 2) * 8 + bits_in_last_byte;
+    let mut chars = Vec::with_capacity(total_bits / 7);
+    let mut acc = 0_u32;
+    let mut acc_bits = 0;
+    for b in &val[1..] {
+        acc |= (*b as u32) << acc_bits;
+        acc_bits += 8;
+        while acc_bits >= 7 && chars.len() < total_bits / 7 {
+            chars.push((acc & 0x7f) as u16);
+            acc >>= 7;
+            acc_bits -= 7;
+        }
+    }
+    Some(chars)
+}
+
+fn decompress_xpress(val: &[u8]) -> Option<Vec<u8>> {
+    if val.len() < 3 {
+        return None;
+    }
+    let size = u16::from_le_bytes([val[1], val[2]]) as usize;
+    let data = lzxpress_decompress(&val[3..], size)?;
+    if data.len() == size {
+        Some(data)
+    } else {
+        None
+    }
+}
+
+fn read_u16(input: &[u8], pos: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(input.get(pos..pos + 2)?.try_into().ok()?) as usize)
+}
+
+fn read_u32(input: &[u8], pos: usize) -> Option<usize> {
+    Some(u32::from_le_bytes(input.get(pos..pos + 4)?.try_into().ok()?) as usize)
+}
+
+/// Plain LZ77 decompression ([MS-XCA] 2.4), also known as LZXPRESS.
+/// None when the input is invalid or decompresses to more than `max_size` bytes.
+pub fn lzxpress_decompress(input: &[u8], max_size: usize) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(max_size);
+    let mut pos = 0;
+    let mut flags = 0;
+    let mut flag_count = 0;
+    let mut last_length_half_byte = None;
+    loop {
+        if flag_count == 0 {
+            if pos == input.len() {
+                break;
+            }
+            flags = read_u32(input, pos)?;
+            pos += 4;
+            flag_count = 32;
+        }
+        flag_count -= 1;
+        if pos == input.len() {
+            break;
+        }
+        if flags & (1 << flag_count) == 0 {
+            if output.len() == max_size {
+                return None;
+            }
+            output.push(input[pos]);
+            pos += 1;
+            continue;
+        }
+        let match_bytes = read_u16(input, pos)?;
+        pos += 2;
+        let mut length = match_bytes % 8;
+        let offset = match_bytes / 8 + 1;
+        if length == 7 {
+            length = match last_length_half_byte.take() {
+                None => {
+                    last_length_half_byte = Some(pos);
+                    pos += 1;
+                    *input.get(pos - 1)? as usize % 16
+                }
+                Some(half) => input[half] as usize / 16,
+            };
+            if length == 15 {
+                length = *input.get(pos)? as usize;
+                pos += 1;
+                if length == 255 {
+                    length = read_u16(input, pos)?;
+                    pos += 2;
+                    if length == 0 {
+                        length = read_u32(input, pos)?;
+                        pos += 4;
+                    }
+                    length = length.checked_sub(15 + 7)?;
+                }
+                length += 15;
+            }
+            length += 7;
+        }
+        length += 3;
+        if output.len() + length > max_size {
+            return None;
+        }
+        let start = output.len().checked_sub(offset)?;
+        // the match may overlap the bytes it produces
+        for i in 0..length {
+            output.push(output[start + i]);
+        }
+    }
+    Some(output)
+}
+
+fn to_hex(val: &[u8]) -> String {
+    val.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn test_lzxpress_decompress() {
+    // examples of [MS-XCA] 3.1
+    let mut input = vec![0x3f, 0x00, 0x00, 0x00];
+    input.extend(b"abcdefghijklmnopqrstuvwxyz");
+    assert_eq!(
+        lzxpress_decompress(&input, 26).unwrap(),
+        b"abcdefghijklmnopqrstuvwxyz"
+    );
+    assert_eq!(lzxpress_decompress(&input, 25), None);
+
+    let input = [
+        0xff, 0xff, 0xff, 0x1f, 0x61, 0x62, 0x63, 0x17, 0x00, 0x0f, 0xff, 0x26, 0x01,
+    ];
+    assert_eq!(
+        lzxpress_decompress(&input, 300).unwrap(),
+        b"abc".repeat(100)
+    );
+    // a match longer than the expected size is not expanded
+    assert_eq!(lzxpress_decompress(&input, 299), None);
+
+    // offset before the start of the output
+    assert_eq!(
+        lzxpress_decompress(&[0x00, 0x00, 0x00, 0x80, 0x08, 0x00], 16),
+        None
+    );
+}
+
+#[test]
+fn test_ese_text() {
+    fn compress_7bit(scheme: u8, text: &str) -> Vec<u8> {
+        let mut out = vec![0];
+        let (mut acc, mut acc_bits) = (0_u32, 0);
+        for c in text.encode_utf16() {
+            acc |= (c as u32 & 0x7f) << acc_bits;
+            acc_bits += 7;
+            while acc_bits >= 8 {
+                out.push(acc as u8);
+                acc >>= 8;
+                acc_bits -= 8;
+            }
+        }
+        let bits_in_last_byte = if acc_bits > 0 {
+            out.push(acc as u8);
+            acc_bits
+        } else {
+            8
+        };
+        out[0] = scheme << 3 | (bits_in_last_byte as u8 - 1);
+        out
+    }
+
+    let summary = "Quarterly report\r\nRevenue and expenses";
+    let compressed = compress_7bit(COMPRESS_7BITUNICODE, summary);
+    assert_eq!(
+        ese_column_text("System_Search_AutoSummary", &compressed),
+        summary
+    );
+    assert_eq!(
+        ese_column_text("System.Search.AutoSummary", &compressed),
+        summary
+    );
+    // odd length, not UTF-16
+    assert_eq!(
+        ese_text(&compress_7bit(COMPRESS_7BITASCII, "notepad.exe")),
+        "notepad.exe"
+    );
+
+    let utf16: Vec<u8> = "abcabcabc"
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    let mut xpress = vec![COMPRESS_XPRESS << 3, utf16.len() as u8, 0x00];
+    // "a\0b\0c\0" then a match of 12 bytes at offset 6
+    xpress.extend([0xff, 0xff, 0xff, 0x03]);
+    xpress.extend(&utf16[..6]);
+    xpress.extend([0x2f, 0x00, 0x02]);
+    assert_eq!(ese_compressed_text(&xpress), "abcabcabc");
+
+    // not compressed
+    let utf16 = |s: &str| -> Vec<u8> { s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect() };
+    assert_eq!(ese_text(&utf16("(555) 0100")), "(555) 0100");
+    // CJK and Hangul text starting with a byte of a compression scheme
+    for text in ["上海", "北京", "下载", "한", "가나다"] {
+        assert_eq!(ese_text(&utf16(text)), text);
+        assert_eq!(
+            ese_column_text("System_ItemPathDisplay", &utf16(text)),
+            text
+        );
+    }
+
+    // Xpress9, unsupported
+    assert_eq!(ese_text(&[0x28, 0x01, 0xd8]), "2801d8");
+}
@@ -1,13 +1,19 @@
 extern crate exitcode;
 use std::process;
 
+use chrono::prelude::*;
 use itertools::Itertools;
 use simple_error::SimpleError;
 use std::collections::HashMap;
 use std::path::Path;
 
+use crate::classify::*;
+use crate::compression::*;
+use crate::properties::*;
 use crate::report::*;
+use crate::secstore::*;
 use crate::shared::*;
+use crate::sids::*;
 use crate::utils::*;
 
 use ese_parser_lib::ese_parser::EseParser;
@@ -17,31 +23,18 @@ use std::io::Write;
 const CACHE_SIZE_ENTRIES: usize = 10;
 const ESENTUTL_MSG: &str = "Use esentutl for recovery (/r) and repair (/p).\nNote that esentutl must be run from a version of Windows that is equal to or newer than the one that generated the database.";
 
+// Column names carry the property id of the Windows build that created the database
+// (4450-System_ItemType), so columns are selected by their canonical name.
 fn prepare_selected_cols(cols: Vec<ColumnInfo>, sel_cols: &Vec<&str>) -> Vec<ColumnInfo> {
-    let mut only_cols: Vec<ColumnInfo> = Vec::new();
-    for c in cols {
-        for sc in sel_cols {
-            if *sc == column_string_part(&c.name) {
-                only_cols.push(c);
-                break;
-            }
-        }
-    }
-    if sel_cols.len() != only_cols.len() {
-        for i in sel_cols {
-            let mut found = false;
-            for j in &only_cols {
-                if *i == column_string_part(&j.name) {
-                    found = true;
-                    break;
-                }
-            }
-            if !found {
-                eprintln!("Requested column {i} not found in table columns");
-            }
+    let resolver = PropertyResolver::new(cols.iter().map(|c| (c.name.as_str(), c.id)));
+    let mut ids = Vec::with_capacity(sel_cols.len());
+    for sc in sel_cols {
+        match resolver.resolve(sc) {
+            Some(id) => ids.push(*id),
+            None => eprintln!("Requested column {sc} not found in table columns"),
         }
     }
-    only_cols
+    cols.into_iter().filter(|c| ids.contains(&c.id)).collect()
 }
 
 fn get_column<T: FromBytes>(
@@ -55,6 +48,57 @@ fn get_column<T: FromBytes>(
     }
 }
 
+// SDID of every document, from the gather table; its DocumentID is the WorkID of the
+// property store. The SDID identifies the security descriptor of the item in the SecStore.
+fn ese_gather_sdids(jdb: &dyn EseDb) -> Result<HashMap<u32, u32>, SimpleError> {
+    let t = "SystemIndex_Gthr";
+    let table_id = jdb.open_table(t)?;
+    let cols = jdb.get_columns(t)?;
+    let find_col = |name: &str| {
+        cols.iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| SimpleError::new(format!("Can't find field '{name}' in {t}")))
+    };
+    let doc_id = find_col("DocumentID")?;
+    let sdid = find_col("SDID")?;
+    let mut sdids = HashMap::new();
+    if jdb.move_row(table_id, ESE_MoveFirst)? {
+        loop {
+            let d = get_column::<u32>(jdb, table_id, doc_id)?;
+            let s = get_column::<u32>(jdb, table_id, sdid)?;
+            if let (Some(d), Some(s)) = (d, s) {
+                sdids.insert(d, s);
+            }
+            if !jdb.move_row(table_id, ESE_MoveNext)? {
+                break;
+            }
+        }
+    }
+    Ok(sdids)
+}
+
+// Security of an item: its SDID, with the owner and ACL of its security descriptor,
+// and the SIDs the indexer requires to return it in results.
+fn insert_security_fields(
+    r: &dyn Report,
+    workId: u32,
+    sdids: &HashMap<u32, u32>,
+    rec: &EseRecord,
+    secstore: &SecStore,
+    sids: &SidResolver,
+) {
+    if !sdids.is_empty() {
+        insert_sdid_fields(r, sdids.get(&workId).copied(), secstore, sids);
+    }
+    sids.set_sid_list_fields(r, "Required");
+    if let Some(v) = rec.get_bytes("RequiredSIDs") {
+        match parse_sid_list(&v) {
+            Some(required) => sids.insert_sid_list(r, "Required", &required),
+            None => r.insert_str_val("RequiredSIDs", ese_text(&v)),
+        }
+    }
+}
+
 pub fn ese_get_hostname(
     jdb: &dyn EseDb,
     table_id: u64,
@@ -67,13 +111,12 @@ pub fn ese_get_hostname(
     let _rollback = DropMe::new(|| {
         let _ = jdb.move_row(table_id, ESE_MoveFirst);
     });
-    let sys_comp_name = columns
-        .iter()
-        .find(|i| column_string_part(&i.name) == "System_ComputerName")
+    let resolver = PropertyResolver::new(columns.iter().map(|c| (c.name.as_str(), c)));
+    let sys_comp_name = resolver
+        .resolve("System.ComputerName")
         .ok_or_else(|| SimpleError::new("Can't find field 'System_ComputerName'".to_string()))?;
-    let sys_item_type = columns
-        .iter()
-        .find(|i| column_string_part(&i.name) == "System_ItemType")
+    let sys_item_type = resolver
+        .resolve("System.ItemType")
         .ok_or_else(|| SimpleError::new("Can't find field 'System_ItemType'".to_string()))?;
     loop {
         if let Some(v) = jdb.get_column(table_id, sys_comp_name.id)? {
@@ -81,12 +124,12 @@ pub fn ese_get_hostname(
             // accept result only if System_ItemType != ".url"
             match jdb.get_column(table_id, sys_item_type.id)? {
                 None => {
-                    return Ok(from_utf16(&v));
+                    return Ok(ese_text(&v));
                 }
                 Some(v2) => {
-                    let item_type = from_utf16(&v2).to_lowercase();
+                    let item_type = ese_text(&v2).to_lowercase();
                     if item_type != ".url" {
-                        return Ok(from_utf16(&v));
+                        return Ok(ese_text(&v));
                     }
                 }
             }
@@ -100,13 +143,97 @@ pub fn ese_get_hostname(
     ))
 }
 
+// Values of a record of SystemIndex_PropertyStore, by property name (System.ItemUrl),
+// whatever the column is called in this database (33-System_ItemUrl).
+// `h` holds the first value of every column, `mv` all values of multi-valued ones.
+struct EseRecord<'a> {
+    h: &'a HashMap<String, Vec<u8>>,
+    mv: &'a HashMap<String, Vec<Vec<u8>>>,
+    columns: &'a PropertyResolver<String>,
+}
+
+impl<'a> EseRecord<'a> {
+    fn new(
+        h: &'a HashMap<String, Vec<u8>>,
+        mv: &'a HashMap<String, Vec<Vec<u8>>>,
+        columns: &'a PropertyResolver<String>,
+    ) -> Self {
+        EseRecord { h, mv, columns }
+    }
+
+    fn get(&self, name: &str) -> Option<&[u8]> {
+        self.columns
+            .resolve(name)
+            .and_then(|col| self.h.get(col))
+            .map(|v| v.as_slice())
+    }
+
+    fn get_all(&self, name: &str) -> Option<&Vec<Vec<u8>>> {
+        self.columns.resolve(name).and_then(|col| self.mv.get(col))
+    }
+}
+
+impl PropertyRecord for EseRecord<'_> {
+    fn get_str(&self, name: &str) -> Option<String> {
+        match self.get_all(name) {
+            Some(_) => self.get_str_list(name).map(|v| v.join(LIST_SEPARATOR)),
+            None => self.get(name).map(|v| ese_column_text(name, v)),
+        }
+    }
+
+    fn get_str_list(&self, name: &str) -> Option<Vec<String>> {
+        match self.get_all(name) {
+            Some(values) => Some(values.iter().flat_map(|v| ese_text_list(v)).collect()),
+            None => self.get(name).map(ese_text_list),
+        }
+    }
+
+    fn get_datetime(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.get(name)
+            .filter(|v| v.len() == 8)
+            .map(|v| get_date_time_from_filetime(u64::from_bytes(v)))
+    }
+
+    fn get_int(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(|v| match v.len() {
+            1 => Some(v[0] as i64),
+            2 => Some(i16::from_bytes(v) as i64),
+            4 => Some(i32::from_bytes(v) as i64),
+            8 => Some(i64::from_bytes(v)),
+            _ => None,
+        })
+    }
+
+    fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name).map(|v| v.iter().any(|b| *b != 0))
+    }
+
+    fn get_float(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(|v| match v.len() {
+            4 => Some(f32::from_bytes(v) as f64),
+            8 => Some(f64::from_bytes(v)),
+            _ => None,
+        })
+    }
+
+    fn get_bytes(&self, name: &str) -> Option<Vec<u8>> {
+        self.get(name).map(|v| v.to_vec())
+    }
+}
+
 pub fn ese_generate_report(
     f: &Path,
+    archive_src: Option<&str>,
     report_prod: &ReportProducer,
+    opts: &DumpOptions,
     status_logger: &mut Box<dyn Write>,
 ) -> Result<(), SimpleError> {
-    writeln!(status_logger, "Processing ESE db: {}", &f.to_string_lossy())
-        .map_err(|e| SimpleError::new(format!("{e}")))?;
+    writeln!(
+        status_logger,
+        "Processing ESE db: {}",
+        archive_src.unwrap_or(&f.to_string_lossy())
+    )
+    .map_err(|e| SimpleError::new(format!("{e}")))?;
     let jdb = Box::new(
         EseParser::load_from_path(CACHE_SIZE_ENTRIES, f)
             .unwrap_or_else(|_| panic!("\nError opening ESE database.\n{ESENTUTL_MSG}\n")),
@@ -123,37 +250,60 @@ pub fn ese_generate_report(
     //let gather_table_fields = dump_file_gather_ese(f)?;
 
     // prepare to query only selected columns
-    let sel_cols = prepare_selected_cols(
-        cols,
-        &vec![
-            "System_ComputerName",
-            "WorkID",
-            // File Report
-            "System_ItemPathDisplay",
-            "System_DateModified",
-            "System_DateCreated",
-            "System_DateAccessed",
-            "System_Size",
-            "System_FileOwner",
-            "System_Search_AutoSummary",
-            "System_Search_GatherTime",
-            "System_ItemType",
-            // IE/Edge History Report
-            "System_ItemUrl",
-            "System_Link_TargetUrl",
-            "System_ItemDate",
-            "System_Title",
-            "System_Link_DateVisited",
-            // Activity History Report
-            "System_ItemNameDisplay",
-            "System_ActivityHistory_StartTime",
-            "System_ActivityHistory_EndTime",
-            "System_Activity_AppDisplayName",
-            "System_ActivityHistory_AppId",
-            "System_Activity_DisplayText",
-            "System_Activity_ContentUri",
-        ],
-    );
+    let mut sel_col_names = vec![
+        "System_ComputerName",
+        "WorkID",
+        // File Report
+        "System_ItemPathDisplay",
+        "System_DateModified",
+        "System_DateCreated",
+        "System_DateAccessed",
+        "System_Size",
+        "System_FileOwner",
+        "System_Search_AutoSummary",
+        "System_Search_GatherTime",
+        "System_ItemType",
+        "System_FileExtension",
+        "System_FileAttributes",
+        "System_Kind",
+        "System_IsFolder",
+        "RequiredSIDs",
+        // IE/Edge History Report
+        "System_ItemUrl",
+        "System_Link_TargetUrl",
+        "System_ItemDate",
+        "System_Title",
+        "System_Link_DateVisited",
+        // Activity History Report
+        "System_ItemNameDisplay",
+        "System_ActivityHistory_StartTime",
+        "System_ActivityHistory_EndTime",
+        "System_Activity_AppDisplayName",
+        "System_ActivityHistory_AppId",
+        "System_Activity_DisplayText",
+        "System_Activity_ContentUri",
+    ];
+    // and the ones the classification rules look at
+    let rule_cols: Vec<String> = opts
+        .classifier
+        .properties()
+        .iter()
+        .map(|p| p.replace('.', "_"))
+        .collect();
+    // and the ones of the reports written with write_properties
+    let report_cols: Vec<String> = all_report_properties()
+        .map(|(name, _)| *name)
+        .chain(GPS_PROPERTIES.iter().copied())
+        .map(column_name)
+        .collect();
+    for c in rule_cols.iter().chain(&report_cols) {
+        if !sel_col_names.contains(&c.as_str()) {
+            sel_col_names.push(c);
+        }
+    }
+    let sel_cols = prepare_selected_cols(cols, &sel_col_names);
+    let col_resolver =
+        PropertyResolver::new(sel_cols.iter().map(|c| (c.name.as_str(), c.name.clone())));
 
     // get System_ComputerName value
     let recovered_hostname = match ese_get_hostname(&*jdb, table_id, &sel_cols) {
@@ -164,8 +314,16 @@ pub fn ese_generate_report(
         }
     };
 
-    let (mut file_rep, mut ie_rep, mut act_rep) = init_reports(
+    let sdids = ese_gather_sdids(&*jdb).unwrap_or_else(|e| {
+        eprintln!("Could not read the SDIDs of the gather table: {e}");
+        HashMap::new()
+    });
+    let secstore = load_secstore(f, status_logger);
+
+    let mut report_set = ReportSet::new(
         f,
+        archive_src,
+        None,
         report_prod,
         &recovered_hostname,
         status_logger,
@@ -173,6 +331,7 @@ pub fn ese_generate_report(
     )?;
 
     let mut h = HashMap::new();
+    let mut mv = HashMap::new();
     loop {
         let mut workId: u32 = 0;
         for c in &sel_cols {
@@ -187,29 +346,64 @@ pub fn ese_generate_report(
                     Err(e) => eprintln!("Error while getting column {} from {}: {}", c.name, t, e),
                 }
             } else {
-                match jdb.get_column(table_id, c.id) {
-                    Ok(r) => match r {
-                        None => {} //println!("Empty field: {}", c.name),
-                        Some(v) => {
-                            h.insert(c.name.clone(), v);
+                match ese_column_values(&*jdb, table_id, c.id) {
+                    Ok(values) => {
+                        if let Some(first) = values.first() {
+                            h.insert(c.name.clone(), first.clone());
+                        }
+                        if values.len() > 1 {
+                            mv.insert(c.name.clone(), values);
                         }
-                    },
+                    }
                     Err(e) => eprintln!("Error while getting column {} from {}: {}", c.name, t, e),
                 }
             }
         }
-        let ie_history = ese_IE_history_record(&mut *ie_rep, workId, &h);
-        let act_history = ese_activity_history_record(&mut *act_rep, workId, &h);
-        if !ie_history && !act_history {
-            ese_dump_file_record(&mut *file_rep, workId, &h);
+        let rec = EseRecord::new(&h, &mv, &col_resolver);
+        let hostname = rec.get_str("System.ComputerName");
+        let category = opts.classifier.classify(&|name| rec.get_str(name));
+        let rep = report_set.get(hostname.as_deref(), category, status_logger)?;
+        if let Some(layout) = report_layout(category) {
+            write_property_row(&mut **rep, workId, &rec, layout);
+            if category == ACTIVITY_HISTORY {
+                insert_activity_fields(&**rep, &rec, &opts.sids);
+                insert_timeline_fields(&**rep, &rec, &opts.timeline);
+                record_app_usage(&mut report_set, hostname.as_deref(), &rec);
+            }
+        } else if category == INTERNET_HISTORY {
+            ese_IE_history_record(&mut **rep, workId, &h);
+        } else {
+            ese_dump_file_record(&mut **rep, workId, &h);
+            insert_file_fields(&**rep, &rec, &opts.sids);
+            insert_mount_fields(&**rep, &rec, &opts.mounts);
+            insert_security_fields(&**rep, workId, &sdids, &rec, &secstore, &opts.sids);
+            write_file_extras(
+                &mut report_set,
+                hostname.as_deref(),
+                workId,
+                &rec,
+                &opts.sids,
+                status_logger,
+            )?;
+        }
+        if let (Some(mft), FILE_REPORT | ACTIVITY_HISTORY) = (&opts.mft, category) {
+            write_mft_comparison(
+                &mut report_set,
+                hostname.as_deref(),
+                workId,
+                &rec,
+                mft,
+                status_logger,
+            )?;
         }
         h.clear();
+        mv.clear();
 
         if !jdb.move_row(table_id, ESE_MoveNext)? {
             break;
         }
     }
-    
+    report_set.finish(status_logger)?;
 
     if report_prod.is_db_dirty(Some(edb_database_state)) {
         if report_prod.get_report_type() == ReportOutput::ToStdout {
@@ -226,14 +420,45 @@ pub fn ese_generate_report(
     Ok(())
 }
 
+// Columns of the File report written by ese_dump_file_record
+const ESE_FILE_COLUMNS: [&str; 11] = [
+    "System_ComputerName",
+    "System_ItemPathDisplay",
+    "System_DateModified",
+    "System_DateCreated",
+    "System_DateAccessed",
+    "System_Size",
+    "System_FileOwner",
+    "System_Search_AutoSummary",
+    "System_Search_GatherTime",
+    "System_ItemType",
+    "System_FileExtension",
+];
+
+// Columns of the Internet History report written by ese_IE_history_record
+const ESE_IE_HISTORY_COLUMNS: [&str; 8] = [
+    "System_ComputerName",
+    "System_ItemUrl",
+    "System_DateModified",
+    "System_Link_TargetUrl",
+    "System_ItemDate",
+    "System_Search_GatherTime",
+    "System_Title",
+    "System_Link_DateVisited",
+];
+
 // File Report
 fn ese_dump_file_record(r: &mut dyn Report, workId: u32, h: &HashMap<String, Vec<u8>>) {
     r.create_new_row(false);
     r.insert_int_val("WorkId", workId as u64);
+    // all columns are set, so that the CSV header has them even if the first record misses some
+    for col in ESE_FILE_COLUMNS {
+        r.set_field(col);
+    }
     for (col, val) in h.iter().sorted() {
         let csp = column_string_part(col);
         match csp {
-            "System_ItemPathDisplay" => r.insert_str_val(csp, from_utf16(val)),
+            "System_ItemPathDisplay" => r.insert_str_val(csp, ese_text(val)),
             "System_DateModified" => r.insert_str_val(
                 csp,
                 format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
@@ -247,17 +472,17 @@ fn ese_dump_file_record(r: &mut dyn Report, workId: u32, h: &HashMap<String, Vec
                 format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
             ),
             "System_Size" => r.insert_int_val(csp, u64::from_bytes(val)),
-            "System_FileOwner" => r.insert_str_val(csp, from_utf16(val)),
-            "System_Search_AutoSummary" => r.insert_str_val(csp, from_utf16(val)),
+            "System_FileOwner" => r.insert_str_val(csp, ese_text(val)),
+            "System_Search_AutoSummary" => r.insert_str_val(csp, ese_compressed_text(val)),
             "System_Search_GatherTime" => r.insert_str_val(
                 csp,
                 format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
             ),
-            "System_ItemType" => r.insert_str_val(csp, from_utf16(val)),
-            "System_ComputerName" => r.insert_str_val(csp, from_utf16(val)),
+            "System_ItemType" => r.insert_str_val(csp, ese_text(val)),
+            "System_FileExtension" => r.insert_str_val(csp, ese_text(val)),
+            "System_ComputerName" => r.insert_str_val(csp, ese_text(val)),
             // "ScopeID" => println!("{}: {}", col, i32::from_bytes(val)),
             // "DocumentID" => println!("{}: {}", col, i32::from_bytes(val)),
-            // "SDID" => println!("{}: {}", col, i32::from_bytes(val)),
             // "LastModified" => println!("{}: {}", col, format_date_time(get_date_time_from_filetime(u64::from_bytes(&val)))),
             // "TransactionFlags" => println!("{}: {}", col, i32::from_bytes(val)),
             // "TransactionExtendedFlags" => println!("{}: {}", col, i32::from_bytes(val)),
@@ -287,23 +512,12 @@ fn ese_dump_file_record(r: &mut dyn Report, workId: u32, h: &HashMap<String, Vec
 }
 
 // IE/Edge History Report
-fn ese_IE_history_record(r: &mut dyn Report, workId: u32, h: &HashMap<String, Vec<u8>>) -> bool {
-    let url = h.get_key_value("33-System_ItemUrl");
-    if url.is_none() {
-        return false;
-    }
-    if let Some((_, val)) = url {
-        let v = from_utf16(val);
-        if !(v.starts_with("iehistory://")
-            || v.starts_with("winrt://")
-                && v.contains("/LS/Desktop/Microsoft Edge/stable/Default/"))
-        {
-            return false;
-        }
-    }
-
+fn ese_IE_history_record(r: &mut dyn Report, workId: u32, h: &HashMap<String, Vec<u8>>) {
     r.create_new_row(false);
     r.insert_int_val("WorkId", workId as u64);
+    for col in ESE_IE_HISTORY_COLUMNS {
+        r.set_field(col);
+    }
     for (col, val) in h.iter().sorted() {
         let csp = column_string_part(col);
         match csp {
@@ -311,8 +525,12 @@ fn ese_IE_history_record(r: &mut dyn Report, workId: u32, h: &HashMap<String, Ve
                 csp,
                 format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
             ),
-            "System_ItemUrl" => r.insert_str_val(csp, from_utf16(val)),
-            "System_Link_TargetUrl" => r.insert_str_val(csp, from_utf16(val)),
+            "System_ItemUrl" => {
+                let v = ese_text(val);
+                insert_browser_fields(r, &v);
+                r.insert_str_val(csp, v);
+            }
+            "System_Link_TargetUrl" => r.insert_str_val(csp, ese_text(val)),
             "System_ItemDate" => r.insert_str_val(
                 csp,
                 format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
@@ -321,62 +539,14 @@ fn ese_IE_history_record(r: &mut dyn Report, workId: u32, h: &HashMap<String, Ve
                 csp,
                 format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
             ),
-            "System_Title" => r.insert_str_val(csp, from_utf16(val)),
+            "System_Title" => r.insert_str_val(csp, ese_text(val)),
             "System_Link_DateVisited" => r.insert_str_val(
                 csp,
                 format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
             ),
-            "System_ComputerName" => r.insert_str_val(csp, from_utf16(val)),
+            "System_ComputerName" => r.insert_str_val(csp, ese_text(val)),
             _ => {}
         }
     }
-    true
 }
 
-// Activity History Report
-fn ese_activity_history_record(
-    r: &mut dyn Report,
-    workId: u32,
-    h: &HashMap<String, Vec<u8>>,
-) -> bool {
-    // record only if "4450-System_ItemType" == "ActivityHistoryItem"
-    let item_type = h.get_key_value("4450-System_ItemType");
-    if item_type.is_none() {
-        return false;
-    }
-    if let Some((_, val)) = item_type {
-        let v = from_utf16(val);
-        if v != "ActivityHistoryItem" {
-            return false;
-        }
-    }
-    r.create_new_row(false);
-    r.insert_int_val("WorkId", workId as u64);
-    for (col, val) in h.iter().sorted() {
-        let csp = column_string_part(col);
-        match csp {
-            "System_ItemNameDisplay" => r.insert_str_val(csp, from_utf16(val)),
-            "System_ItemUrl" => r.insert_str_val(csp, from_utf16(val)), // TODO: get UserSID from here
-            "System_ActivityHistory_StartTime" => r.insert_str_val(
-                csp,
-                format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
-            ),
-            "System_ActivityHistory_EndTime" => r.insert_str_val(
-                csp,
-                format_date_time(get_date_time_from_filetime(u64::from_bytes(val))),
-            ),
-            "System_Activity_AppDisplayName" => r.insert_str_val(csp, from_utf16(val)),
-            "System_ActivityHistory_AppId" => r.insert_str_val(csp, from_utf16(val)),
-            "System_Activity_DisplayText" => r.insert_str_val(csp, from_utf16(val)),
-            "System_Activity_ContentUri" => {
-                let v = from_utf16(val);
-                r.insert_str_val("VolumeId", find_guid(&v, "VolumeId="));
-                r.insert_str_val("ObjectId", find_guid(&v, "ObjectId="));
-                r.insert_str_val(csp, v);
-            }
-            "System_ComputerName" => r.insert_str_val(csp, from_utf16(val)),
-            _ => {}
-        }
-    }
-    true
-}
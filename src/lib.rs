@@ -3,11 +3,14 @@
 pub mod report;
 #[allow(non_camel_case_types)]
 pub mod utils;
+pub mod compression;
 pub mod mssql;
+pub mod properties;
 
-use crate::utils::column_string_part;
+use crate::compression::{ese_column_text, ese_text_list};
+use crate::properties::{canonical_property_name, split_utf16_list, PropertyResolver};
 use ::function_name::named;
-use log::{debug, error, info, trace};
+use log::{debug, error, info, trace, warn};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, str, string::String};
 
@@ -24,6 +27,8 @@ pub enum ColumnType {
     Integer,
     DateTime,
     GUID,
+    /// Multi-valued string, all values of the property.
+    StringList,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,6 +123,10 @@ pub trait FieldReader {
     fn get_str(&mut self, id: &FldId) -> Option<String>;
     fn get_guid(&mut self, id: &FldId) -> Option<String>;
     fn get_datetime(&mut self, id: &FldId) -> Option<DateTime<Utc>>;
+
+    fn get_str_list(&mut self, id: &FldId) -> Option<Vec<String>> {
+        self.get_str(id).map(|s| vec![s])
+    }
 }
 
 //--------------------------------------------------------------------
@@ -125,7 +134,7 @@ use ese_parser_lib::vartime::{get_date_time_from_filetime, VariantTimeToSystemTi
 use ese_parser_lib::{ese_parser::EseParser, ese_trait::*, DbState};
 
 use std::{fs::File, io::BufReader};
-use utils::{find_guid, from_utf16};
+use utils::{ese_column_values, find_guid};
 
 const CACHE_SIZE_ENTRIES: usize = 10;
 
@@ -206,7 +215,10 @@ impl FieldReader for EseReader {
             if !name.is_empty() {
                 match cols
                     .iter()
-                    .find(|col| col.name == name || column_string_part(&col.name) == name)
+                    .find(|col| {
+                        col.name == name
+                            || canonical_property_name(&col.name) == canonical_property_name(&name)
+                    })
                 {
                     Some(col_info) => {
                         col_infos.insert(
@@ -305,7 +317,7 @@ impl FieldReader for EseReader {
             return None;
         }
         match self.jdb.get_column(self.table, self.col_infos[id].0) {
-            Ok(r) => r.map(|v| from_utf16(v.as_slice())),
+            Ok(r) => r.map(|v| ese_column_text(id, v.as_slice())),
             Err(_e) => panic!("{id} - error: {_e}"),
         }
     }
@@ -316,6 +328,17 @@ impl FieldReader for EseReader {
         }
         None
     }
+
+    fn get_str_list(&mut self, id: &FldId) -> Option<Vec<String>> {
+        if !self.col_infos.contains_key(id) {
+            return None;
+        }
+        match ese_column_values(&*self.jdb, self.table, self.col_infos[id].0) {
+            Ok(values) if values.is_empty() => None,
+            Ok(values) => Some(values.iter().flat_map(|v| ese_text_list(v)).collect()),
+            Err(_e) => panic!("{id} - error: {_e}"),
+        }
+    }
 }
 
 //--------------------------------------------------------------------
@@ -333,6 +356,7 @@ type Session<'connection> = OwningHandle<Box<Connection>, Box<Statement<'connect
 
 pub struct SqlReader<'a> {
     last_work_id: u64,
+    property_ids: PropertyResolver<i64>,
     code_col_dict: CodeColDict,
     row_values: RefCell<SqlRow>,
     session: Session<'a>,
@@ -341,6 +365,7 @@ pub struct SqlReader<'a> {
 impl SqlReader<'_> {
     pub fn new(db_path: &str) -> Self {
         let conn = Connection::open_with_flags(db_path, OpenFlags::new().with_read_only()).unwrap();
+        let property_ids = Self::read_property_ids(&conn);
         let sql = "select WorkId, * from SystemIndex_1_PropertyStore order by WorkId";
         let session = Session::new_with_fn(Box::new(conn), unsafe {
             |x| Box::new((*x).prepare(sql).unwrap())
@@ -348,12 +373,40 @@ impl SqlReader<'_> {
 
         SqlReader {
             session,
+            property_ids,
             row_values: RefCell::new(SqlRow::new()),
             code_col_dict: CodeColDict::new(),
             last_work_id: 0,
         }
     }
 
+    // ColumnIds differ between Windows builds, so columns may be configured by property name
+    // (System.ItemType) and are resolved through the metadata of the database.
+    fn read_property_ids(conn: &Connection) -> PropertyResolver<i64> {
+        let mut ids = Vec::<(String, i64)>::new();
+        match conn.prepare("select Id, Name from SystemIndex_1_PropertyStore_Metadata") {
+            Ok(mut s) => {
+                while let Ok(State::Row) = s.next() {
+                    let id = s.read::<i64, _>("Id");
+                    let name = s.read::<String, _>("Name");
+                    if let (Ok(id), Ok(name)) = (id, name) {
+                        ids.push((name, id));
+                    }
+                }
+            }
+            Err(e) => error!("Could not read SystemIndex_1_PropertyStore_Metadata: {e}"),
+        }
+        PropertyResolver::new(ids.iter().map(|(name, id)| (name.as_str(), *id)))
+    }
+
+    // A numeric name is used as the ColumnId as is.
+    fn resolve_column_id(&self, name: &str) -> Option<ColCode> {
+        if name.parse::<i64>().is_ok() {
+            return Some(name.to_string());
+        }
+        self.property_ids.resolve(name).map(|id| id.to_string())
+    }
+
     fn first_row(&mut self) -> bool {
         self.last_work_id = 0;
         self.session.reset().is_ok()
@@ -417,11 +470,18 @@ impl<'a> FieldReader for SqlReader<'a> {
                     debug!("{pair:?} -> {ok}");
                     ok
                 })
-                .map(|(no, pair)| {
-                    (
-                        pair.sql.name.clone(),
+                .filter_map(|(no, pair)| match self.resolve_column_id(&pair.sql.name) {
+                    Some(code) => Some((
+                        code,
                         ConstrainedField::new(&pair.title, &pair.sql.constraint, no),
-                    )
+                    )),
+                    None => {
+                        warn!(
+                            "Unknown property '{}', column '{}' left empty",
+                            pair.sql.name, pair.title
+                        );
+                        None
+                    }
                 }),
         );
 
@@ -543,6 +603,24 @@ impl<'a> FieldReader for SqlReader<'a> {
         }
         None
     }
+
+    // multi-valued properties are stored as UTF-16 values separated by a null character
+    fn get_str_list(&mut self, id: &FldId) -> Option<Vec<String>> {
+        if id.is_empty() {
+            return None;
+        }
+
+        if let Some(v) = self.get_value(id) {
+            return match v {
+                sqlite::Value::String(x) => Some(vec![x]),
+                sqlite::Value::Binary(vec) => Some(split_utf16_list(&vec)),
+                sqlite::Value::Null => None,
+                _ => panic!("unexpected {v:?} for {id}"),
+            };
+        }
+
+        None
+    }
 }
 
 //--------------------------------------------------------------------
@@ -574,6 +652,10 @@ impl<R: Report + ?Sized> Report for Box<R> {
         (**self).insert_int_val(f, n)
     }
 
+    fn insert_str_list_val(&self, f: &str, values: Vec<String>) {
+        (**self).insert_str_list_val(f, values)
+    }
+
     fn is_some_val_in_record(&self) -> bool {
         (**self).is_some_val_in_record()
     }
@@ -639,7 +721,7 @@ pub fn do_reports(
                         constraint: None,
                     },
                     sql: Column {
-                        name: "567".to_string(),
+                        name: "System.ItemType".to_string(),
                         constraint: None,
                     },
                 };
@@ -869,6 +951,11 @@ pub fn do_reports(
                             report.reporter.insert_str_val(col.title.as_str(), guid);
                         }
                     }
+                    ColumnType::StringList => {
+                        if let Some(values) = reader.get_str_list(col_id) {
+                            report.reporter.insert_str_list_val(col.title.as_str(), values);
+                        }
+                    }
                 }
             }
 
@@ -6,27 +6,42 @@ use clap::Parser;
 
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use simple_error::SimpleError;
+use tempdir::TempDir;
 
 
 
+pub mod archive;
+pub mod classify;
+pub mod compression;
 pub mod ese;
+pub mod mft;
+pub mod mount;
+pub mod registry;
 pub mod report;
+pub mod secstore;
 pub mod shared;
+pub mod sids;
 pub mod sqlite;
+pub mod timeline;
 pub mod utils;
 pub mod mssql;
+pub mod properties;
 
+use crate::archive::*;
 use crate::ese::*;
 use crate::report::*;
+use crate::shared::*;
 use crate::sqlite::*;
+use crate::timeline::*;
 
 
 fn dump(
     input_dir: &PathBuf,
     report_prod: &ReportProducer,
+    opts: &mut DumpOptions,
     status_logger: &mut Box<dyn Write>,
 ) -> Result<(), SimpleError> {
     let mut processed = 0;
@@ -36,18 +51,24 @@ fn dump(
                 let p = entry.path();
                 let metadata = fs::metadata(&p).unwrap();
                 if metadata.is_dir() {
-                    dump(&p, report_prod, status_logger)?;
+                    dump(&p, report_prod, opts, status_logger)?;
+                } else if is_archive(&p) {
+                    // an unreadable archive does not stop the scan of the directory
+                    if let Err(e) = dump_archive(&p, report_prod, opts, status_logger) {
+                        eprintln!("{e}");
+                        writeln!(status_logger, "Skipped archive {}", p.to_string_lossy())
+                            .map_err(|e| SimpleError::new(format!("{e}")))?;
+                    }
+                } else if is_timeline_database(&p) {
+                    writeln!(
+                        status_logger,
+                        "Found Windows Timeline database {} (joined with Activity History)",
+                        p.to_string_lossy()
+                    )
+                    .map_err(|e| SimpleError::new(format!("{e}")))?;
                 } else if is_valid_file(&p) {
                     processed += 1;
-                    let ext = p
-                        .extension()
-                        .and_then(|e| e.to_str())
-                        .map(|s| s.to_lowercase());
-                    let _ = match ext.as_deref() {
-                        Some("edb") => ese_generate_report(&p, report_prod, status_logger),
-                        Some("db") => sqlite_generate_report(&p, report_prod, status_logger),
-                        _ => continue,
-                    };
+                    let _ = generate_report(&p, None, report_prod, opts, status_logger);
                 }
             }
         }
@@ -73,19 +94,77 @@ fn dump(
     Ok(())
 }
 
-fn is_valid_file(p: &PathBuf) -> bool {
-    let is_valid_name = p
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .map_or(false, |name| {
-            let name = name.to_ascii_lowercase();
-            name == "windows" || name.starts_with("s-1-")
-        });
-    let is_valid_ext = p.extension().and_then(|e| e.to_str()).map_or(false, |ext| {
-        let ext = ext.to_ascii_lowercase();
-        ext == "edb" || ext == "db"
-    });
-    is_valid_name && is_valid_ext
+// Databases found inside a collection archive are extracted to a temporary directory,
+// which is removed once their reports are written.
+fn dump_archive(
+    archive: &Path,
+    report_prod: &ReportProducer,
+    opts: &mut DumpOptions,
+    status_logger: &mut Box<dyn Write>,
+) -> Result<(), SimpleError> {
+    writeln!(status_logger, "Scanning archive: {}", &archive.to_string_lossy())
+        .map_err(|e| SimpleError::new(format!("{e}")))?;
+    let tmp_dir = TempDir::new("sidr").map_err(|e| SimpleError::new(format!("{e}")))?;
+    let extracted = extract_databases(archive, tmp_dir.path()).map_err(|e| {
+        SimpleError::new(format!(
+            "Could not read archive '{}': {e}",
+            archive.to_string_lossy()
+        ))
+    })?;
+    // the Activity History of the archive is joined with its own Timeline databases,
+    // read before its reports are written
+    let outer_timeline = if extracted.timelines.is_empty() {
+        None
+    } else {
+        let mut timeline = Timeline::default();
+        for db in &extracted.timelines {
+            writeln!(
+                status_logger,
+                "Found Windows Timeline database {} (joined with Activity History)",
+                db.source
+            )
+            .map_err(|e| SimpleError::new(format!("{e}")))?;
+            if let Err(e) = timeline.load_as(&db.path, &db.source) {
+                eprintln!("Could not read Windows Timeline database '{}': {e}", db.source);
+            }
+        }
+        Some(std::mem::replace(&mut opts.timeline, timeline))
+    };
+    let dbs = extracted.databases;
+    for db in &dbs {
+        let _ = generate_report(&db.path, Some(&db.source), report_prod, opts, status_logger);
+    }
+    if let Some(timeline) = outer_timeline {
+        opts.timeline = timeline;
+    }
+    if !dbs.is_empty() {
+        writeln!(
+            status_logger,
+            "\nFound {} Windows Search database(s) in {}",
+            dbs.len(),
+            archive.to_string_lossy()
+        )
+        .map_err(|e| SimpleError::new(format!("{e}")))?;
+    }
+    Ok(())
+}
+
+fn generate_report(
+    p: &Path,
+    archive_src: Option<&str>,
+    report_prod: &ReportProducer,
+    opts: &DumpOptions,
+    status_logger: &mut Box<dyn Write>,
+) -> Result<(), SimpleError> {
+    let ext = p
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|s| s.to_lowercase());
+    match ext.as_deref() {
+        Some("edb") => ese_generate_report(p, archive_src, report_prod, opts, status_logger),
+        Some("db") => sqlite_generate_report(p, archive_src, report_prod, opts, status_logger),
+        _ => Ok(()),
+    }
 }
 
 /// Copyright 2023, Aon
@@ -129,7 +208,9 @@ fn is_valid_file(p: &PathBuf) -> bool {
 #[derive(Parser)]
 #[command(author, version, about, long_about)]
 struct Cli {
-    /// Path to input directory (which will be recursively scanned for Windows.edb and Windows.db).
+    /// Path to input directory (which will be recursively scanned for Windows.edb and Windows.db)
+    /// or to a collection archive (.zip, .tar, .tar.gz) such as KAPE, Velociraptor or CyLR output.
+    /// Archives found while scanning a directory are processed as well.
     indir: PathBuf,
 
     /// Output report format
@@ -151,6 +232,47 @@ struct Cli {
     /// Name of the database where the tables will be created (required if --report-type is to-database)
     #[arg(short, long, requires_if("to-database", "report_type"), value_name = "DATABASE")]
     database: Option<String>,
+
+    /// Write separate reports for every hostname found in System_ComputerName
+    /// (by default all records of a database go to reports named after a single hostname)
+    #[arg(long)]
+    split_by_hostname: bool,
+
+    /// Offline SOFTWARE registry hive, its ProfileList resolves the user SIDs in Activity History
+    /// to profile paths and user names
+    #[arg(long, value_name = "HIVE")]
+    software: Option<PathBuf>,
+
+    /// Offline SAM registry hive, its local users and groups resolve SIDs to account names
+    #[arg(long, value_name = "HIVE")]
+    sam: Option<PathBuf>,
+
+    /// $MFT of the volume the databases come from: indexed files that are gone from it, moved or
+    /// whose timestamps disagree are written to HOSTNAME_MFT_Comparison_Report_DateTime
+    #[arg(long, value_name = "FILE")]
+    mft: Option<PathBuf>,
+
+    /// Drive letter of the volume of --mft
+    #[arg(long, value_name = "LETTER", default_value_t = 'C', requires = "mft")]
+    mft_drive: char,
+
+    /// Volume of the collected host mounted locally, as drive letter and mount point (C=/mnt/c);
+    /// may be repeated. Indexed paths on it get a FileStatus of Exists, Deleted or Changed
+    #[arg(long, value_name = "LETTER=DIR")]
+    mount: Vec<String>,
+
+    /// CSV file mapping user SIDs to names: SID,Name[,ProfilePath] per line
+    #[arg(long, value_name = "CSV")]
+    sid_map: Option<PathBuf>,
+
+    /// YAML file with additional record classification rules (see src/default_rules.yaml for the format)
+    #[arg(long, value_name = "YAML")]
+    rules: Option<PathBuf>,
+
+    /// Also write the locations of geotagged photos and videos as GeoJSON
+    /// (HOSTNAME_Media_Locations_DateTime.geojson)
+    #[arg(long)]
+    geojson: bool,
 }
 
 fn main() -> Result<(), SimpleError> {
@@ -162,11 +284,55 @@ fn main() -> Result<(), SimpleError> {
     };
 
 
-    write_reports(&output_dir, cli.format, cli.report_type, &cli.indir, cli.instance, cli.database)?;
+    let mut opts = DumpOptions::default();
+    if let Some(software) = &cli.software {
+        opts.sids.load_profile_list(software)?;
+    }
+    if let Some(sam) = &cli.sam {
+        opts.sids.load_sam(sam)?;
+    }
+    if let Some(sid_map) = &cli.sid_map {
+        opts.sids.load_sid_map(sid_map)?;
+    }
+    if let Some(rules) = &cli.rules {
+        opts.classifier.add_rules_file(rules)?;
+    }
+    if let Some(mft) = &cli.mft {
+        let mut mft = mft::Mft::open(mft)?;
+        mft.set_drive(cli.mft_drive);
+        opts.mft = Some(mft);
+    }
+    for mount in &cli.mount {
+        opts.mounts.add(mount)?;
+    }
+    // Timeline databases are read before any Activity History record is written
+    if cli.indir.is_dir() {
+        for db in find_timeline_databases(&cli.indir) {
+            if let Err(e) = opts.timeline.load(&db) {
+                eprintln!(
+                    "Could not read Windows Timeline database '{}': {e}",
+                    db.to_string_lossy()
+                );
+            }
+        }
+    }
+
+    write_reports(
+        &output_dir,
+        cli.format,
+        cli.report_type,
+        &cli.indir,
+        cli.instance,
+        cli.database,
+        cli.split_by_hostname,
+        cli.geojson,
+        &mut opts,
+    )?;
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn write_reports(
     rep_dir: &PathBuf,
     format: ReportFormat,
@@ -174,14 +340,23 @@ fn write_reports(
     input_dir: &PathBuf,
     instance: Option<String>,
     database: Option<String>,
+    split_by_hostname: bool,
+    geojson: bool,
+    opts: &mut DumpOptions,
 ) -> Result<(), SimpleError> {
-    let rep_producer = ReportProducer::new(rep_dir.as_path(), format, report_type, instance, database);
+    let mut rep_producer = ReportProducer::new(rep_dir.as_path(), format, report_type, instance, database);
+    rep_producer.set_split_by_hostname(split_by_hostname);
+    rep_producer.set_geojson(geojson);
     let mut status_logger: Box<dyn std::io::Write> = match report_type {
         ReportOutput::ToStdout => Box::new(std::io::sink()),
         ReportOutput::ToFile => Box::new(std::io::stdout()),
         ReportOutput::ToDatabase => Box::new(std::io::stdout()),
     };
-    dump(&input_dir, &rep_producer, &mut status_logger)?;
+    if input_dir.is_file() && is_archive(input_dir) {
+        dump_archive(input_dir, &rep_producer, opts, &mut status_logger)?;
+    } else {
+        dump(input_dir, &rep_producer, opts, &mut status_logger)?;
+    }
     Ok(())
 }
 
@@ -195,6 +370,8 @@ fn warn_dirty() {
         report_dir.as_path(),
         ReportFormat::Csv,
         ReportOutput::ToFile,
+        None,
+        None,
     );
     let ese_path = PathBuf::from("tests/testdata/Windows.edb");
     assert!(ese_path.exists());
@@ -216,12 +393,22 @@ fn test_generate_reports() {
         ReportFormat::Csv,
         ReportOutput::ToFile,
         &input_dir,
+        None,
+        None,
+        false,
+        false,
+        &mut DumpOptions::default(),
     );
     let _ = write_reports(
         &report_dir,
         ReportFormat::Json,
         ReportOutput::ToFile,
         &input_dir,
+        None,
+        None,
+        false,
+        false,
+        &mut DumpOptions::default(),
     );
 
     match fs::read_dir(goldenfiles_dir.clone()) {
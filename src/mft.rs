@@ -0,0 +1,461 @@
+use chrono::prelude::*;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::utils::*;
+
+// Minimal read-only parser for an NTFS $MFT, enough to rebuild the path of every entry
+// and read its $STANDARD_INFORMATION, $FILE_NAME and $OBJECT_ID attributes.
+
+const DEFAULT_RECORD_SIZE: usize = 1024;
+const SECTOR_SIZE: usize = 512;
+const ROOT_ENTRY: u64 = 5;
+const ENTRY_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
+
+const FLAG_IN_USE: u16 = 0x01;
+
+const ATTR_STANDARD_INFORMATION: u32 = 0x10;
+const ATTR_FILE_NAME: u32 = 0x30;
+const ATTR_OBJECT_ID: u32 = 0x40;
+const ATTR_END: u32 = 0xFFFF_FFFF;
+
+const NAMESPACE_DOS: u8 = 2;
+
+/// Timestamps of $STANDARD_INFORMATION or $FILE_NAME.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MftTimes {
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MftEntry {
+    pub entry: u64,
+    pub sequence: u16,
+    pub in_use: bool,
+    /// Path on the volume without the drive letter, e.g. \Users\alice\notes.txt
+    pub path: String,
+    pub si: MftTimes,
+    pub fn_times: MftTimes,
+    pub object_id: Option<String>,
+}
+
+// $FILE_NAME of an entry, the one of the long name if there are several.
+#[derive(Default)]
+struct FileName {
+    parent: u64,
+    name: String,
+    namespace: u8,
+    times: MftTimes,
+}
+
+#[derive(Default)]
+struct RawEntry {
+    sequence: u16,
+    in_use: bool,
+    si: MftTimes,
+    file_name: Option<FileName>,
+    object_id: Option<String>,
+}
+
+/// Entries of a $MFT by path, file reference number and object ID.
+#[derive(Default)]
+pub struct Mft {
+    /// Drive letter of the volume, C unless set with `set_drive`.
+    drive: char,
+    entries: Vec<MftEntry>,
+    by_path: HashMap<String, Vec<usize>>,
+    by_entry: HashMap<u64, usize>,
+    by_object_id: HashMap<String, usize>,
+}
+
+fn u16_at(b: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(b.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_at(b: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(b.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn u64_at(b: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(b.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+fn filetime_at(b: &[u8], offset: usize) -> Option<DateTime<Utc>> {
+    match u64_at(b, offset)? {
+        0 => None,
+        ft => Some(get_date_time_from_filetime(ft)),
+    }
+}
+
+fn times_at(b: &[u8], created: usize, modified: usize, accessed: usize) -> MftTimes {
+    MftTimes {
+        created: filetime_at(b, created),
+        modified: filetime_at(b, modified),
+        accessed: filetime_at(b, accessed),
+    }
+}
+
+/// Key of a path for lookups: lowercase, without the drive letter.
+pub fn mft_path_key(path: &str) -> String {
+    let path = match path.as_bytes() {
+        [d, b':', ..] if d.is_ascii_alphabetic() => &path[2..],
+        _ => path,
+    };
+    path.replace('/', "\\").to_lowercase()
+}
+
+// Replaces the last 2 bytes of every sector with the update sequence array entries.
+fn apply_fixups(record: &mut [u8]) -> Option<()> {
+    let usa_offset = u16_at(record, 4)? as usize;
+    let usa_count = u16_at(record, 6)? as usize;
+    let usn = u16_at(record, usa_offset)?;
+    for i in 1..usa_count {
+        let end = i * SECTOR_SIZE;
+        if end > record.len() {
+            break;
+        }
+        if u16_at(record, end - 2)? != usn {
+            return None;
+        }
+        let fixup = record.get(usa_offset + i * 2..usa_offset + i * 2 + 2)?.to_vec();
+        record[end - 2..end].copy_from_slice(&fixup);
+    }
+    Some(())
+}
+
+// Attributes of a record, as type and resident value; non-resident ones are skipped.
+fn resident_attributes(record: &[u8]) -> Vec<(u32, &[u8])> {
+    let mut attrs = Vec::new();
+    let mut offset = u16_at(record, 0x14).unwrap_or(0) as usize;
+    while let (Some(typ), Some(len)) = (u32_at(record, offset), u32_at(record, offset + 4)) {
+        if typ == ATTR_END || len < 0x18 {
+            break;
+        }
+        let non_resident = record.get(offset + 8).copied().unwrap_or(1);
+        if non_resident == 0 {
+            let value_len = u32_at(record, offset + 0x10).unwrap_or(0) as usize;
+            let value_offset = u16_at(record, offset + 0x14).unwrap_or(0) as usize;
+            if let Some(value) = record.get(offset + value_offset..offset + value_offset + value_len) {
+                attrs.push((typ, value));
+            }
+        }
+        offset += len as usize;
+    }
+    attrs
+}
+
+fn parse_file_name(value: &[u8]) -> Option<FileName> {
+    let name_len = *value.get(0x40)? as usize;
+    let namespace = *value.get(0x41)?;
+    Some(FileName {
+        parent: u64_at(value, 0)? & ENTRY_MASK,
+        name: from_utf16(value.get(0x42..0x42 + name_len * 2)?),
+        namespace,
+        times: times_at(value, 0x08, 0x10, 0x20),
+    })
+}
+
+// Merges the attributes of a record into its entry, or into the base entry of an extension record.
+fn parse_record(entry: u64, record: &mut [u8], raw: &mut HashMap<u64, RawEntry>) -> Option<()> {
+    if record.get(0..4)? != b"FILE" {
+        return None;
+    }
+    apply_fixups(record)?;
+    let flags = u16_at(record, 0x16)?;
+    let base = u64_at(record, 0x20)? & ENTRY_MASK;
+    let owner = if base == 0 { entry } else { base };
+    let e = raw.entry(owner).or_default();
+    if base == 0 {
+        e.sequence = u16_at(record, 0x10)?;
+        e.in_use = flags & FLAG_IN_USE != 0;
+    }
+    for (typ, value) in resident_attributes(record) {
+        match typ {
+            ATTR_STANDARD_INFORMATION => e.si = times_at(value, 0x00, 0x08, 0x18),
+            ATTR_FILE_NAME => {
+                if let Some(file_name) = parse_file_name(value) {
+                    let better = match &e.file_name {
+                        None => true,
+                        Some(f) => f.namespace == NAMESPACE_DOS && file_name.namespace != NAMESPACE_DOS,
+                    };
+                    if better {
+                        e.file_name = Some(file_name);
+                    }
+                }
+            }
+            ATTR_OBJECT_ID => e.object_id = format_guid(value.get(0..16)?),
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+impl Mft {
+    pub fn open(path: &Path) -> Result<Self, SimpleError> {
+        let f = File::open(path).map_err(|e| {
+            SimpleError::new(format!("Can't read $MFT '{}': {e}", path.to_string_lossy()))
+        })?;
+        Self::from_reader(BufReader::new(f))
+    }
+
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, SimpleError> {
+        let mut raw = HashMap::<u64, RawEntry>::new();
+        let mut record = vec![0u8; DEFAULT_RECORD_SIZE];
+        let mut entry = 0;
+        loop {
+            match reader.read_exact(&mut record) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(SimpleError::new(format!("Can't read $MFT: {e}"))),
+            }
+            if entry == 0 {
+                if record.get(0..4) != Some(b"FILE") {
+                    return Err(SimpleError::new("Not a $MFT (missing FILE signature)"));
+                }
+                // the allocated size of the record, 4096 on 4Kn disks
+                let size = u32_at(&record, 0x1C).unwrap_or(0) as usize;
+                if size > DEFAULT_RECORD_SIZE && size.is_multiple_of(SECTOR_SIZE) && size <= 0x10000 {
+                    let mut rest = vec![0u8; size - DEFAULT_RECORD_SIZE];
+                    reader
+                        .read_exact(&mut rest)
+                        .map_err(|e| SimpleError::new(format!("Can't read $MFT: {e}")))?;
+                    record.extend(rest);
+                }
+            }
+            parse_record(entry, &mut record, &mut raw);
+            entry += 1;
+        }
+        Ok(Self::from_raw(raw))
+    }
+
+    fn from_raw(raw: HashMap<u64, RawEntry>) -> Self {
+        let mut mft = Mft {
+            drive: 'C',
+            ..Default::default()
+        };
+        let mut paths = HashMap::<u64, String>::new();
+        let mut numbers: Vec<&u64> = raw.keys().collect();
+        numbers.sort();
+        for n in numbers {
+            let e = &raw[n];
+            let path = match &e.file_name {
+                Some(_) => entry_path(*n, &raw, &mut paths),
+                None => continue,
+            };
+            let idx = mft.entries.len();
+            mft.by_path.entry(mft_path_key(&path)).or_default().push(idx);
+            mft.by_entry.insert(*n, idx);
+            if let Some(id) = &e.object_id {
+                mft.by_object_id.insert(id.to_uppercase(), idx);
+            }
+            mft.entries.push(MftEntry {
+                entry: *n,
+                sequence: e.sequence,
+                in_use: e.in_use,
+                path,
+                si: e.si,
+                fn_times: e.file_name.as_ref().map(|f| f.times).unwrap_or_default(),
+                object_id: e.object_id.clone(),
+            });
+        }
+        mft
+    }
+
+    pub fn drive(&self) -> char {
+        self.drive
+    }
+
+    pub fn set_drive(&mut self, drive: char) {
+        self.drive = drive.to_ascii_uppercase();
+    }
+
+    /// Whether a path is on the volume of this $MFT.
+    pub fn covers(&self, path: &str) -> bool {
+        let mut chars = path.chars();
+        matches!(
+            (chars.next(), chars.next(), chars.next()),
+            (Some(d), Some(':'), Some('\\')) if d.to_ascii_uppercase() == self.drive
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry of a path (C:\Users\alice\notes.txt); an allocated one if any.
+    pub fn find_path(&self, path: &str) -> Option<&MftEntry> {
+        let found = self.by_path.get(&mft_path_key(path))?;
+        found
+            .iter()
+            .map(|i| &self.entries[*i])
+            .find(|e| e.in_use)
+            .or_else(|| found.first().map(|i| &self.entries[*i]))
+    }
+
+    /// The entry of a file reference number (System.FileFRN): entry number and sequence.
+    pub fn find_frn(&self, frn: u64) -> Option<&MftEntry> {
+        let e = &self.entries[*self.by_entry.get(&(frn & ENTRY_MASK))?];
+        if e.sequence == (frn >> 48) as u16 {
+            Some(e)
+        } else {
+            None
+        }
+    }
+
+    pub fn find_object_id(&self, object_id: &str) -> Option<&MftEntry> {
+        self.by_object_id
+            .get(&object_id.to_uppercase())
+            .map(|i| &self.entries[*i])
+    }
+}
+
+// Follows the parent references up to the root; entries whose parent is gone go under \$Orphan.
+fn entry_path(n: u64, raw: &HashMap<u64, RawEntry>, paths: &mut HashMap<u64, String>) -> String {
+    let mut names = Vec::new();
+    let mut cur = n;
+    let mut prefix = String::new();
+    while cur != ROOT_ENTRY {
+        if let Some(p) = paths.get(&cur) {
+            prefix = p.clone();
+            break;
+        }
+        match raw.get(&cur).and_then(|e| e.file_name.as_ref()) {
+            Some(f) if names.len() < 256 && f.parent != cur => {
+                names.push(f.name.as_str());
+                cur = f.parent;
+            }
+            _ => {
+                prefix = "\\$Orphan".to_string();
+                break;
+            }
+        }
+    }
+    let mut path = prefix;
+    for name in names.iter().rev() {
+        path.push('\\');
+        path.push_str(name);
+    }
+    paths.insert(n, path.clone());
+    path
+}
+
+#[cfg(test)]
+fn filetime(dt: DateTime<Utc>) -> u64 {
+    ((dt.timestamp() + 11_644_473_600) * 10_000_000) as u64
+}
+
+#[cfg(test)]
+fn attribute(typ: u32, value: &[u8]) -> Vec<u8> {
+    let mut a = vec![0u8; 0x18];
+    let len = (0x18 + value.len() + 7) & !7;
+    a[0..4].copy_from_slice(&typ.to_le_bytes());
+    a[4..8].copy_from_slice(&(len as u32).to_le_bytes());
+    a[0x10..0x14].copy_from_slice(&(value.len() as u32).to_le_bytes());
+    a[0x14..0x16].copy_from_slice(&0x18_u16.to_le_bytes());
+    a.extend_from_slice(value);
+    a.resize(len, 0);
+    a
+}
+
+/// A 1024 byte FILE record with fixups applied.
+#[cfg(test)]
+pub fn mft_record(
+    sequence: u16,
+    in_use: bool,
+    parent: u64,
+    name: &str,
+    si_created: DateTime<Utc>,
+    fn_created: DateTime<Utc>,
+    object_id: Option<[u8; 16]>,
+) -> Vec<u8> {
+    let mut r = vec![0u8; DEFAULT_RECORD_SIZE];
+    r[0..4].copy_from_slice(b"FILE");
+    r[4..6].copy_from_slice(&0x30_u16.to_le_bytes());
+    r[6..8].copy_from_slice(&3_u16.to_le_bytes());
+    r[0x10..0x12].copy_from_slice(&sequence.to_le_bytes());
+    r[0x14..0x16].copy_from_slice(&0x38_u16.to_le_bytes());
+    r[0x16..0x18].copy_from_slice(&(in_use as u16).to_le_bytes());
+    r[0x1C..0x20].copy_from_slice(&(DEFAULT_RECORD_SIZE as u32).to_le_bytes());
+
+    let mut si = vec![0u8; 0x48];
+    si[0..8].copy_from_slice(&filetime(si_created).to_le_bytes());
+    si[8..16].copy_from_slice(&filetime(si_created).to_le_bytes());
+    let mut fname = vec![0u8; 0x42];
+    fname[0..8].copy_from_slice(&parent.to_le_bytes());
+    fname[8..16].copy_from_slice(&filetime(fn_created).to_le_bytes());
+    fname[16..24].copy_from_slice(&filetime(fn_created).to_le_bytes());
+    fname[0x40] = name.encode_utf16().count() as u8;
+    fname[0x41] = 1;
+    fname.extend(name.encode_utf16().flat_map(|c| c.to_le_bytes()));
+
+    let mut attrs = attribute(ATTR_STANDARD_INFORMATION, &si);
+    attrs.extend(attribute(ATTR_FILE_NAME, &fname));
+    if let Some(id) = object_id {
+        attrs.extend(attribute(ATTR_OBJECT_ID, &id));
+    }
+    attrs.extend(ATTR_END.to_le_bytes());
+    r[0x38..0x38 + attrs.len()].copy_from_slice(&attrs);
+
+    // update sequence: the last 2 bytes of each sector are saved in the array
+    let usn = 0x0001_u16;
+    r[0x30..0x32].copy_from_slice(&usn.to_le_bytes());
+    for i in 1..=2 {
+        let end = i * SECTOR_SIZE;
+        let saved = [r[end - 2], r[end - 1]];
+        r[0x30 + i * 2..0x32 + i * 2].copy_from_slice(&saved);
+        r[end - 2..end].copy_from_slice(&usn.to_le_bytes());
+    }
+    r
+}
+
+#[test]
+fn test_mft() {
+    let t0 = Utc.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap();
+    let t1 = Utc.with_ymd_and_hms(2023, 6, 7, 8, 9, 10).unwrap();
+    let mut data = Vec::new();
+    for n in 0..8_u64 {
+        let rec = match n {
+            5 => mft_record(5, true, 5, ".", t0, t0, None),
+            6 => mft_record(1, true, 5, "Users", t0, t0, None),
+            7 => mft_record(3, true, 6, "notes.txt", t0, t1, Some([0x11; 16])),
+            _ => mft_record(1, true, 5, &format!("$Meta{n}"), t0, t0, None),
+        };
+        data.extend(rec);
+    }
+    data.extend(mft_record(2, false, 6, "gone.txt", t1, t1, None));
+
+    let mft = Mft::from_reader(data.as_slice()).unwrap();
+    assert_eq!(mft.len(), 9);
+    let notes = mft.find_path("C:\\Users\\NOTES.txt").unwrap();
+    assert_eq!(notes.entry, 7);
+    assert_eq!(notes.path, "\\Users\\notes.txt");
+    assert_eq!(notes.si.created, Some(t0));
+    assert_eq!(notes.fn_times.created, Some(t1));
+    assert_eq!(
+        notes.object_id.as_deref(),
+        Some("{11111111-1111-1111-1111-111111111111}")
+    );
+    assert_eq!(mft.find_frn(7 | 3 << 48).unwrap().entry, 7);
+    assert_eq!(mft.find_frn(7 | 2 << 48), None);
+    assert_eq!(
+        mft.find_object_id("{11111111-1111-1111-1111-111111111111}").unwrap().entry,
+        7
+    );
+    assert!(mft.covers("c:\\Users\\notes.txt"));
+    assert!(!mft.covers("D:\\notes.txt"));
+    assert!(!mft.covers("mapi16://{S-1-5-21-1-2-3-1001}/"));
+    let gone = mft.find_path("C:\\Users\\gone.txt").unwrap();
+    assert!(!gone.in_use);
+    assert_eq!(mft.find_path("C:\\Users\\other.txt"), None);
+
+    assert!(Mft::from_reader(&[0u8; 2048][..]).is_err());
+}
@@ -0,0 +1,166 @@
+use chrono::{DateTime, Utc};
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Modification times closer than this are the same; FAT volumes store them with 2 second precision.
+const MTIME_TOLERANCE_SECONDS: i64 = 2;
+
+/// Drives of the collected host mounted locally, to check indexed paths against.
+#[derive(Default)]
+pub struct MountedVolumes {
+    mounts: HashMap<char, PathBuf>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FileStatus {
+    Exists,
+    Deleted,
+    Changed,
+}
+
+impl FileStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exists => "Exists",
+            Self::Deleted => "Deleted",
+            Self::Changed => "Changed",
+        }
+    }
+}
+
+/// A path of the index as found on the mounted volume.
+#[derive(Debug, PartialEq)]
+pub struct CurrentFile {
+    pub status: FileStatus,
+    pub size: Option<u64>,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+impl MountedVolumes {
+    /// Adds a mapping of a drive letter to a local directory: C=/mnt/c
+    pub fn add(&mut self, mapping: &str) -> Result<(), SimpleError> {
+        let invalid = || SimpleError::new(format!("Invalid mount '{mapping}', expected LETTER=DIR"));
+        let (drive, dir) = mapping.split_once('=').ok_or_else(invalid)?;
+        let drive = drive.trim_end_matches(':');
+        let mut chars = drive.chars();
+        let letter = match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => c.to_ascii_uppercase(),
+            _ => return Err(invalid()),
+        };
+        let dir = PathBuf::from(dir);
+        if !dir.is_dir() {
+            return Err(SimpleError::new(format!(
+                "Mount point '{}' is not a directory",
+                dir.to_string_lossy()
+            )));
+        }
+        self.mounts.insert(letter, dir);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mounts.is_empty()
+    }
+
+    /// Local path of an indexed path (C:\Users\alice\notes.txt), None if its drive is not mounted.
+    /// Names are matched case-insensitively, as on Windows.
+    pub fn local_path(&self, path: &str) -> Option<PathBuf> {
+        let mut chars = path.chars();
+        let drive = match (chars.next(), chars.next()) {
+            (Some(d), Some(':')) if d.is_ascii_alphabetic() => d.to_ascii_uppercase(),
+            _ => return None,
+        };
+        let mut local = self.mounts.get(&drive)?.clone();
+        for name in path[2..].split('\\').filter(|n| !n.is_empty()) {
+            let exact = local.join(name);
+            local = if exact.symlink_metadata().is_ok() {
+                exact
+            } else {
+                find_entry(&local, name).unwrap_or(exact)
+            };
+        }
+        Some(local)
+    }
+
+    /// Status of an indexed file on the mounted volume, None if its drive is not mounted.
+    /// A file is Changed when its size or modification time differ from the indexed ones.
+    pub fn check(
+        &self,
+        path: &str,
+        size: Option<i64>,
+        modified: Option<DateTime<Utc>>,
+        is_folder: bool,
+    ) -> Option<CurrentFile> {
+        let local = self.local_path(path)?;
+        let Ok(metadata) = fs::metadata(&local) else {
+            return Some(CurrentFile {
+                status: FileStatus::Deleted,
+                size: None,
+                modified: None,
+            });
+        };
+        let current_size = metadata.is_file().then_some(metadata.len());
+        let current_modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+        let size_changed = match (size, current_size) {
+            (Some(s), Some(c)) => s as u64 != c,
+            _ => false,
+        };
+        let modified_changed = match (modified, current_modified) {
+            (Some(m), Some(c)) => (m - c).num_seconds().abs() > MTIME_TOLERANCE_SECONDS,
+            _ => false,
+        };
+        // the modification time of a folder changes whenever its content does
+        let status = if !is_folder && (size_changed || modified_changed) {
+            FileStatus::Changed
+        } else {
+            FileStatus::Exists
+        };
+        Some(CurrentFile {
+            status,
+            size: current_size,
+            modified: current_modified,
+        })
+    }
+}
+
+fn find_entry(dir: &Path, name: &str) -> Option<PathBuf> {
+    let name = name.to_lowercase();
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .find(|e| e.file_name().to_string_lossy().to_lowercase() == name)
+        .map(|e| e.path())
+}
+
+#[test]
+fn test_mounted_volumes() {
+    let dir = tempdir::TempDir::new("sidr_test").unwrap();
+    std::fs::create_dir_all(dir.path().join("Users").join("alice")).unwrap();
+    let notes = dir.path().join("Users").join("alice").join("notes.txt");
+    std::fs::write(&notes, b"12345").unwrap();
+    let modified = DateTime::<Utc>::from(std::fs::metadata(&notes).unwrap().modified().unwrap());
+
+    let mut mounts = MountedVolumes::default();
+    assert!(mounts.add("C").is_err());
+    assert!(mounts.add("CD=/").is_err());
+    mounts.add(&format!("c:={}", dir.path().to_string_lossy())).unwrap();
+
+    assert_eq!(mounts.local_path("C:\\USERS\\Alice\\notes.txt"), Some(notes.clone()));
+    assert_eq!(mounts.local_path("D:\\notes.txt"), None);
+
+    let check = |path, size, modified| mounts.check(path, size, modified, false).map(|f| f.status);
+    let path = "C:\\Users\\alice\\notes.txt";
+    assert_eq!(check(path, Some(5), Some(modified)), Some(FileStatus::Exists));
+    assert_eq!(check(path, Some(4), Some(modified)), Some(FileStatus::Changed));
+    assert_eq!(
+        check(path, Some(5), Some(modified - chrono::Duration::hours(1))),
+        Some(FileStatus::Changed)
+    );
+    assert_eq!(check("C:\\Users\\alice\\gone.txt", Some(5), None), Some(FileStatus::Deleted));
+    assert_eq!(check("D:\\Users\\alice\\notes.txt", Some(5), None), None);
+    let current = mounts.check(path, None, None, false).unwrap();
+    assert_eq!(current.size, Some(5));
+    assert_eq!(current.modified, Some(modified));
+}
@@ -6,6 +6,7 @@ use simple_error::SimpleError;
 use regex::Regex;
 
 
+use crate::properties::*;
 use crate::report::*;
 
 pub struct ReportMSSQL<'env> {
@@ -20,6 +21,21 @@ pub struct ReportMSSQL<'env> {
     row_counter: Cell<usize>, // Tracks the number of rows since the last flush
 }
 
+fn owned_columns(columns: &[(&str, &'static str)]) -> Vec<(String, &'static str)> {
+    columns
+        .iter()
+        .map(|(name, typ)| (name.to_string(), *typ))
+        .collect()
+}
+
+// Columns of the reports written with `write_properties`.
+fn property_columns(layout: &ReportLayout) -> Vec<(String, &'static str)> {
+    layout
+        .columns()
+        .map(|(name, typ)| (name, sql_type(typ)))
+        .collect()
+}
+
 impl<'env> ReportMSSQL<'env> {
     pub fn new(
         table_name: &str,
@@ -114,6 +130,78 @@ impl<'env> ReportMSSQL<'env> {
         Ok(())
     }
 
+    // Column name and SQL type of every table, in insertion order.
+    fn table_columns(&self) -> Result<Vec<(String, &'static str)>, SimpleError> {
+        let mut columns = match self.report_suffix {
+            // categories added with --rules are written like the File report
+            Some(ReportSuffix::FileReport) | Some(ReportSuffix::Unknown) => owned_columns(&[
+                ("WorkId", "BIGINT"),
+                ("System_ComputerName", "NVARCHAR(MAX)"),
+                ("System_ItemPathDisplay", "NVARCHAR(MAX)"),
+                ("System_DateModified", "DATETIME2"),
+                ("System_DateCreated", "DATETIME2"),
+                ("System_DateAccessed", "DATETIME2"),
+                ("System_Size", "BIGINT"),
+                ("System_FileOwner", "NVARCHAR(MAX)"),
+                ("FileOwnerSID", "NVARCHAR(MAX)"),
+                ("FileOwnerName", "NVARCHAR(MAX)"),
+                ("SDID", "BIGINT"),
+                ("RequiredSIDs", "NVARCHAR(MAX)"),
+                ("RequiredNames", "NVARCHAR(MAX)"),
+                ("OwnerSID", "NVARCHAR(MAX)"),
+                ("OwnerName", "NVARCHAR(MAX)"),
+                ("GroupSID", "NVARCHAR(MAX)"),
+                ("GroupName", "NVARCHAR(MAX)"),
+                ("ACL", "NVARCHAR(MAX)"),
+                ("System_Search_AutoSummary", "NVARCHAR(MAX)"),
+                ("System_Search_GatherTime", "DATETIME2"),
+                ("System_ItemType", "NVARCHAR(MAX)"),
+                ("System_FileExtension", "NVARCHAR(MAX)"),
+                ("System_FileAttributes", "BIGINT"),
+                ("FileAttributeNames", "NVARCHAR(MAX)"),
+                ("System_Kind", "NVARCHAR(MAX)"),
+                ("System_IsFolder", "NVARCHAR(MAX)"),
+                ("ParentFolder", "NVARCHAR(MAX)"),
+                ("FileName", "NVARCHAR(MAX)"),
+                ("Extension", "NVARCHAR(MAX)"),
+                ("UserProfile", "NVARCHAR(MAX)"),
+                ("FileStatus", "NVARCHAR(MAX)"),
+                ("CurrentSize", "BIGINT"),
+                ("CurrentDateModified", "DATETIME2"),
+            ]),
+            Some(ReportSuffix::InternetHistory) => owned_columns(&[
+                ("WorkId", "BIGINT"),
+                ("System_ItemUrl", "NVARCHAR(MAX)"),
+                ("Browser", "NVARCHAR(MAX)"),
+                ("Channel", "NVARCHAR(MAX)"),
+                ("Profile", "NVARCHAR(MAX)"),
+                ("System_ItemDate", "DATETIME2"),
+                ("System_Link_TargetUrl", "NVARCHAR(MAX)"),
+                ("System_Search_GatherTime", "DATETIME2"),
+                ("System_Title", "NVARCHAR(MAX)"),
+                ("System_Link_DateVisited", "DATETIME2"),
+            ]),
+            Some(ReportSuffix::ActivityHistory) => property_columns(&ACTIVITY_REPORT),
+            Some(ReportSuffix::Email) => property_columns(&EMAIL_REPORT),
+            Some(ReportSuffix::Contacts) => property_columns(&CONTACTS_REPORT),
+            Some(ReportSuffix::Calendar) => property_columns(&CALENDAR_REPORT),
+            Some(ReportSuffix::Tasks) => property_columns(&TASKS_REPORT),
+            Some(ReportSuffix::DocumentMetadata) => property_columns(&DOCUMENT_REPORT),
+            Some(ReportSuffix::Media) => property_columns(&MEDIA_REPORT),
+            Some(ReportSuffix::CloudFiles) => property_columns(&CLOUD_REPORT),
+            Some(ReportSuffix::Shortcuts) => property_columns(&SHORTCUT_REPORT),
+            Some(ReportSuffix::Applications) => property_columns(&APPLICATION_REPORT),
+            Some(ReportSuffix::MftComparison) => property_columns(&MFT_REPORT),
+            None => return Err(SimpleError::new("Invalid report suffix")),
+        };
+        // set when the database was read from a collection archive
+        columns.push(("SourceArchive".to_string(), "NVARCHAR(MAX)"));
+        // set for per-user databases
+        columns.push(("DbOwnerSID".to_string(), "NVARCHAR(MAX)"));
+        columns.push(("DbOwnerName".to_string(), "NVARCHAR(MAX)"));
+        Ok(columns)
+    }
+
     pub fn create_table(&self) -> Result<(), SimpleError> {
         let guard = self.connection.lock().unwrap(); // Get the MutexGuard
         let connection = guard
@@ -121,79 +209,16 @@ impl<'env> ReportMSSQL<'env> {
             .ok_or_else(|| SimpleError::new("No active database connection"))?;
 
         // Define the SQL query to create the table
-        let query = match self.report_suffix{
-            Some(ReportSuffix::FileReport) => {
-                format!(
-                    "CREATE TABLE [{}] (
-                        [WorkId] BIGINT NULL,
-                        [System_ComputerName] NVARCHAR(MAX) NULL,
-                        [System_ItemPathDisplay] NVARCHAR(MAX) NULL,
-                        [System_DateModified] DATETIME2 NULL,
-                        [System_DateCreated] DATETIME2 NULL,
-                        [System_DateAccessed] DATETIME2 NULL,
-                        [System_Size] BIGINT NULL,
-                        [System_FileOwner] NVARCHAR(MAX) NULL,
-                        [System_Search_AutoSummary] NVARCHAR(MAX) NULL,
-                        [System_Search_GatherTime] DATETIME2 NULL,
-                        [System_ItemType] NVARCHAR(MAX) NULL
-                    )",
-                    self.table_name
-                )
-            }
-            Some(ReportSuffix::ActivityHistory) => {
-            format!(
-                "CREATE TABLE [{}] (
-                    [WorkId] BIGINT NULL,
-                    [System_ComputerName] NVARCHAR(MAX) NULL,
-                    [System_ItemPathDisplay] NVARCHAR(MAX) NULL,
-                    [System_DateModified] DATETIME2 NULL,
-                    [System_DateCreated] DATETIME2 NULL,
-                    [System_DateAccessed] DATETIME2 NULL,
-                    [System_Size] BIGINT NULL,
-                    [System_FileOwner] NVARCHAR(MAX) NULL,
-                    [System_Search_AutoSummary] NVARCHAR(MAX) NULL,
-                    [System_Search_GatherTime] DATETIME2 NULL,
-                    [System_ItemType] NVARCHAR(MAX) NULL
-                )",
-                self.table_name
-            )
-            }
-            Some(ReportSuffix::InternetHistory) => {
-                format!(
-                "CREATE TABLE [{}] (
-                    [WorkId] BIGINT NULL,
-                    [System_ItemUrl] NVARCHAR(MAX) NULL,
-                    [System_ItemDate] DATETIME2 NULL,
-                    [System_Link_TargetUrl] NVARCHAR(MAX) NULL,
-                    [System_Search_GatherTime] DATETIME2 NULL,
-                    [System_Title] NVARCHAR(MAX) NULL,
-                    [System_Link_DateVisited] DATETIME2 NULL
-                )",
-                self.table_name
-            )
-            }
-            _ => {
-                return Err(SimpleError::new("Invalid report suffix"));
-            }
-        };
-        
-   
-        /*format!(
-            "CREATE TABLE [{}] (
-                [WorkId] BIGINT NULL,
-                [System_ComputerName] NVARCHAR(MAX) NULL,
-                [System_ItemPathDisplay] NVARCHAR(MAX) NULL,
-                [System_DateModified] DATETIME NULL,
-                [System_DateCreated] DATETIME NULL,
-                [System_DateAccessed] DATETIME NULL,
-                [System_Size] BIGINT NULL,
-                [System_FileOwner] NVARCHAR(MAX) NULL,
-                [System_Search_AutoSummary] NVARCHAR(MAX) NULL,
-                [System_Search_GatherTime] DATETIME NULL,
-                [System_ItemType] NVARCHAR(MAX) NULL
-            )",
-            self.table_name
-        );*/
+        let columns = self
+            .table_columns()?
+            .iter()
+            .map(|(name, typ)| format!("[{name}] {typ} NULL"))
+            .collect::<Vec<_>>();
+        let query = format!(
+            "CREATE TABLE [{}] (\n{}\n)",
+            self.table_name,
+            columns.join(",\n")
+        );
 
         // Execute the query
         connection
@@ -208,49 +233,12 @@ impl<'env> ReportMSSQL<'env> {
         let values = self.values.borrow();
 
         // Define the column order based on the report suffix
-        let column_order = match self.report_suffix {
-            Some(ReportSuffix::FileReport) => vec![
-                "WorkId",
-                "System_ComputerName",
-                "System_ItemPathDisplay",
-                "System_DateModified",
-                "System_DateCreated",
-                "System_DateAccessed",
-                "System_Size",
-                "System_FileOwner",
-                "System_Search_AutoSummary",
-                "System_Search_GatherTime",
-                "System_ItemType",
-            ],
-            Some(ReportSuffix::ActivityHistory) => vec![
-                "WorkId",
-                "System_ComputerName",
-                "System_ItemPathDisplay",
-                "System_DateModified",
-                "System_DateCreated",
-                "System_DateAccessed",
-                "System_Size",
-                "System_FileOwner",
-                "System_Search_AutoSummary",
-                "System_Search_GatherTime",
-                "System_ItemType",
-            ],
-            Some(ReportSuffix::InternetHistory) => vec![
-                "WorkId",
-                "System_ItemUrl",
-                "System_ItemDate",
-                "System_Link_TargetUrl",
-                "System_Search_GatherTime",
-                "System_Title",
-                "System_Link_DateVisited",
-            ],
-            _ => return Err(SimpleError::new("Invalid report suffix")),
-        };
+        let column_order = self.table_columns()?;
 
         // Map the provided values to their corresponding columns
         let mut column_values = vec!["NULL".to_string(); column_order.len()];
         for (field, value) in values.iter() {
-            if let Some(index) = column_order.iter().position(|&col| col == field) {
+            if let Some(index) = column_order.iter().position(|(col, _)| col == field) {
                 column_values[index] = format!("'{}'", value.replace("'", "''")); // Escape single quotes
             }
         }
@@ -287,44 +275,11 @@ impl<'env> ReportMSSQL<'env> {
         // Execute the accumulated query
         if !query_builder.is_empty() {
             // Define the column list based on the report suffix
-            let column_list = match self.report_suffix {
-                Some(ReportSuffix::FileReport) => vec![
-                    "[WorkId]",
-                    "[System_ComputerName]",
-                    "[System_ItemPathDisplay]",
-                    "[System_DateModified]",
-                    "[System_DateCreated]",
-                    "[System_DateAccessed]",
-                    "[System_Size]",
-                    "[System_FileOwner]",
-                    "[System_Search_AutoSummary]",
-                    "[System_Search_GatherTime]",
-                    "[System_ItemType]",
-                ],
-                Some(ReportSuffix::ActivityHistory) => vec![
-                    "[WorkId]",
-                    "[System_ComputerName]",
-                    "[System_ItemPathDisplay]",
-                    "[System_DateModified]",
-                    "[System_DateCreated]",
-                    "[System_DateAccessed]",
-                    "[System_Size]",
-                    "[System_FileOwner]",
-                    "[System_Search_AutoSummary]",
-                    "[System_Search_GatherTime]",
-                    "[System_ItemType]",
-                ],
-                Some(ReportSuffix::InternetHistory) => vec![
-                    "[WorkId]",
-                    "[System_ItemUrl]",
-                    "[System_ItemDate]",
-                    "[System_Link_TargetUrl]",
-                    "[System_Search_GatherTime]",
-                    "[System_Title]",
-                    "[System_Link_DateVisited]",
-                ],
-                _ => return Err(SimpleError::new("Invalid report suffix")),
-            };
+            let column_list = self
+                .table_columns()?
+                .iter()
+                .map(|(name, _)| format!("[{name}]"))
+                .collect::<Vec<_>>();
 
             // Construct the full query
             let query = format!(
@@ -0,0 +1,582 @@
+use chrono::prelude::*;
+use std::collections::HashMap;
+
+use crate::report::*;
+use crate::utils::*;
+
+/// How the value of a property is decoded and written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PropType {
+    Str,
+    /// Multi-valued string (recipients, authors, keywords).
+    StrList,
+    DateTime,
+    Int,
+    Bool,
+    Float,
+    Guid,
+}
+
+/// A property of a report column, by its canonical name (System.Message.FromAddress).
+/// The column is named with the dots replaced (System_Message_FromAddress).
+pub type Property = (&'static str, PropType);
+
+/// A record of the property store, with values looked up by canonical property name,
+/// whatever the database format.
+pub trait PropertyRecord {
+    fn get_str(&self, name: &str) -> Option<String>;
+    fn get_str_list(&self, name: &str) -> Option<Vec<String>>;
+    fn get_datetime(&self, name: &str) -> Option<DateTime<Utc>>;
+    fn get_int(&self, name: &str) -> Option<i64>;
+    fn get_bool(&self, name: &str) -> Option<bool>;
+    fn get_float(&self, name: &str) -> Option<f64>;
+    /// Undecoded value, for vectors such as System.GPS.Latitude.
+    fn get_bytes(&self, name: &str) -> Option<Vec<u8>>;
+
+    fn get_guid(&self, name: &str) -> Option<String> {
+        self.get_bytes(name).and_then(|v| format_guid(&v))
+    }
+}
+
+pub fn column_name(property: &str) -> String {
+    property.replace('.', "_")
+}
+
+/// Report column name of a property, whichever way the database spells it: the property
+/// name (System.ItemType) or an ESE column name with the id prefix of the Windows build
+/// that created the database (4450-System_ItemType).
+pub fn canonical_property_name(name: &str) -> String {
+    column_name(column_string_part(name))
+}
+
+/// Maps canonical property names to the key a particular database stores them under:
+/// the ESE column name or the SQLite ColumnId. Both differ between Windows builds,
+/// so they are taken from the database itself rather than hard-coded.
+pub struct PropertyResolver<K> {
+    keys: HashMap<String, K>,
+}
+
+impl<K> PropertyResolver<K> {
+    pub fn new<'n>(columns: impl IntoIterator<Item = (&'n str, K)>) -> Self {
+        PropertyResolver {
+            keys: columns
+                .into_iter()
+                .map(|(name, key)| (canonical_property_name(name), key))
+                .collect(),
+        }
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<&K> {
+        self.keys.get(&canonical_property_name(name))
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// Splits a multi-valued string: UTF-16 values separated by a null character.
+pub fn split_utf16_list(val: &[u8]) -> Vec<String> {
+    from_utf16(val)
+        .split('\0')
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Value of a report column.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropValue {
+    Str(String),
+    /// Values of a multi-valued property.
+    StrList(Vec<String>),
+    Int(u64),
+}
+
+/// Column name and value of every property present in the record.
+pub fn read_properties(rec: &dyn PropertyRecord, props: &[Property]) -> Vec<(String, PropValue)> {
+    let mut values = Vec::new();
+    for (name, typ) in props {
+        let v = match typ {
+            PropType::Str => rec.get_str(name).map(PropValue::Str),
+            PropType::StrList => rec.get_str_list(name).map(PropValue::StrList),
+            PropType::DateTime => rec
+                .get_datetime(name)
+                .map(|v| PropValue::Str(format_date_time(v))),
+            PropType::Int => rec.get_int(name).map(|v| PropValue::Int(v as u64)),
+            PropType::Bool => rec.get_bool(name).map(|v| PropValue::Str(v.to_string())),
+            PropType::Float => rec.get_float(name).map(|v| PropValue::Str(v.to_string())),
+            PropType::Guid => rec.get_guid(name).map(PropValue::Str),
+        };
+        if let Some(v) = v {
+            values.push((column_name(name), v));
+        }
+    }
+    values
+}
+
+pub fn write_values(r: &dyn Report, values: &[(String, PropValue)]) {
+    for (col, v) in values {
+        match v {
+            PropValue::Str(s) => r.insert_str_val(col, s.clone()),
+            PropValue::StrList(v) => r.insert_str_list_val(col, v.clone()),
+            PropValue::Int(i) => r.insert_int_val(col, *i),
+        }
+    }
+}
+
+/// Writes the value of every property present in the record.
+pub fn write_properties(r: &dyn Report, rec: &dyn PropertyRecord, props: &[Property]) {
+    write_values(r, &read_properties(rec, props));
+}
+
+/// SQL type of a column written by `write_properties`.
+pub fn sql_type(typ: PropType) -> &'static str {
+    match typ {
+        PropType::Str | PropType::StrList | PropType::Guid => "NVARCHAR(MAX)",
+        PropType::DateTime => "DATETIME2",
+        PropType::Int => "BIGINT",
+        PropType::Bool => "BIT",
+        PropType::Float => "FLOAT",
+    }
+}
+
+// Indexed Outlook items (mapi:// and mapi16:// URLs).
+pub const EMAIL_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemUrl", PropType::Str),
+    ("System.ItemFolderPathDisplay", PropType::Str),
+    ("System.Message.Store", PropType::Str),
+    ("System.Message.MessageClass", PropType::Str),
+    ("System.Message.ConversationID", PropType::Str),
+    ("System.Subject", PropType::Str),
+    ("System.Message.FromName", PropType::StrList),
+    ("System.Message.FromAddress", PropType::StrList),
+    ("System.Message.SenderName", PropType::Str),
+    ("System.Message.SenderAddress", PropType::Str),
+    ("System.Message.ToName", PropType::StrList),
+    ("System.Message.ToAddress", PropType::StrList),
+    ("System.Message.CcName", PropType::StrList),
+    ("System.Message.CcAddress", PropType::StrList),
+    ("System.Message.BccName", PropType::StrList),
+    ("System.Message.BccAddress", PropType::StrList),
+    ("System.Message.DateSent", PropType::DateTime),
+    ("System.Message.DateReceived", PropType::DateTime),
+    ("System.Message.HasAttachments", PropType::Bool),
+    ("System.Message.AttachmentNames", PropType::StrList),
+    ("System.Size", PropType::Int),
+    ("System.Search.AutoSummary", PropType::Str),
+    ("System.Search.GatherTime", PropType::DateTime),
+];
+
+// People app and Outlook contacts.
+pub const CONTACTS_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemUrl", PropType::Str),
+    ("System.ItemNameDisplay", PropType::Str),
+    ("System.Contact.FullName", PropType::Str),
+    ("System.Contact.FirstName", PropType::Str),
+    ("System.Contact.LastName", PropType::Str),
+    ("System.Contact.NickName", PropType::Str),
+    ("System.Company", PropType::Str),
+    ("System.Contact.JobTitle", PropType::Str),
+    ("System.Contact.EmailAddress", PropType::Str),
+    ("System.Contact.EmailAddresses", PropType::StrList),
+    ("System.Contact.PrimaryTelephone", PropType::Str),
+    ("System.Contact.MobileTelephone", PropType::Str),
+    ("System.Contact.BusinessTelephone", PropType::Str),
+    ("System.Contact.HomeTelephone", PropType::Str),
+    ("System.Contact.BusinessAddress", PropType::Str),
+    ("System.Contact.HomeAddress", PropType::Str),
+    ("System.Contact.Birthday", PropType::DateTime),
+    ("System.DateModified", PropType::DateTime),
+    ("System.Search.GatherTime", PropType::DateTime),
+];
+
+// Calendar app and Outlook appointments and meetings.
+pub const CALENDAR_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemUrl", PropType::Str),
+    ("System.Subject", PropType::Str),
+    ("System.StartDate", PropType::DateTime),
+    ("System.EndDate", PropType::DateTime),
+    ("System.Calendar.Location", PropType::Str),
+    ("System.Calendar.OrganizerName", PropType::Str),
+    ("System.Calendar.OrganizerAddress", PropType::Str),
+    ("System.Calendar.RequiredAttendeeNames", PropType::StrList),
+    ("System.Calendar.RequiredAttendeeAddresses", PropType::StrList),
+    ("System.Calendar.OptionalAttendeeNames", PropType::StrList),
+    ("System.Calendar.OptionalAttendeeAddresses", PropType::StrList),
+    ("System.Calendar.IsRecurring", PropType::Bool),
+    ("System.Calendar.ShowTimeAsText", PropType::Str),
+    ("System.Search.AutoSummary", PropType::Str),
+    ("System.Search.GatherTime", PropType::DateTime),
+];
+
+// To Do and Outlook tasks.
+pub const TASKS_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemUrl", PropType::Str),
+    ("System.Subject", PropType::Str),
+    ("System.Task.Owner", PropType::Str),
+    ("System.Task.CompletionStatus", PropType::Str),
+    ("System.StartDate", PropType::DateTime),
+    ("System.DueDate", PropType::DateTime),
+    ("System.DateCompleted", PropType::DateTime),
+    ("System.Importance", PropType::Int),
+    ("System.IsFlaggedComplete", PropType::Bool),
+    ("System.Search.AutoSummary", PropType::Str),
+    ("System.Search.GatherTime", PropType::DateTime),
+];
+
+// Authorship metadata of Office and other documents.
+pub const DOCUMENT_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemPathDisplay", PropType::Str),
+    ("System.Title", PropType::Str),
+    ("System.Subject", PropType::Str),
+    ("System.Author", PropType::StrList),
+    ("System.Document.LastAuthor", PropType::Str),
+    ("System.Document.Manager", PropType::Str),
+    ("System.Company", PropType::Str),
+    ("System.Keywords", PropType::StrList),
+    ("System.Category", PropType::StrList),
+    ("System.ApplicationName", PropType::Str),
+    ("System.Document.Template", PropType::Str),
+    ("System.Document.RevisionNumber", PropType::Str),
+    ("System.Document.DateCreated", PropType::DateTime),
+    ("System.Document.DateSaved", PropType::DateTime),
+    ("System.Document.DatePrinted", PropType::DateTime),
+    // in 100-nanosecond units
+    ("System.Document.TotalEditingTime", PropType::Int),
+    ("System.Document.PageCount", PropType::Int),
+    ("System.Document.WordCount", PropType::Int),
+    ("System.Document.CharacterCount", PropType::Int),
+    ("System.Document.LineCount", PropType::Int),
+    ("System.Document.ParagraphCount", PropType::Int),
+    ("System.Document.SlideCount", PropType::Int),
+    ("System.DateModified", PropType::DateTime),
+];
+
+// Photos, videos and music. The decimal GPS coordinates are added by `gps_coordinate`.
+pub const MEDIA_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemPathDisplay", PropType::Str),
+    ("System.ItemType", PropType::Str),
+    ("System.Title", PropType::Str),
+    ("System.Photo.DateTaken", PropType::DateTime),
+    ("System.Photo.CameraManufacturer", PropType::Str),
+    ("System.Photo.CameraModel", PropType::Str),
+    ("System.Image.HorizontalSize", PropType::Int),
+    ("System.Image.VerticalSize", PropType::Int),
+    ("System.Video.FrameWidth", PropType::Int),
+    ("System.Video.FrameHeight", PropType::Int),
+    // in 100-nanosecond units
+    ("System.Media.Duration", PropType::Int),
+    ("System.Media.DateEncoded", PropType::DateTime),
+    ("System.Music.Artist", PropType::StrList),
+    ("System.Music.AlbumTitle", PropType::Str),
+    ("System.DateModified", PropType::DateTime),
+];
+
+// Files under a cloud sync root (OneDrive, SharePoint libraries synced by OneDrive, ...).
+// The decoded provider, sync and pin state are added by `insert_cloud_fields`.
+pub const CLOUD_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemPathDisplay", PropType::Str),
+    ("System.Size", PropType::Int),
+    ("System.DateModified", PropType::DateTime),
+    ("System.StorageProviderId", PropType::Str),
+    ("System.StorageProviderFileIdentifier", PropType::Str),
+    ("System.StorageProviderFileRemoteUri", PropType::Str),
+    ("System.StorageProviderState", PropType::Int),
+    ("System.SyncTransferStatus", PropType::Int),
+    ("System.FilePlaceholderStatus", PropType::Int),
+    ("System.FileAttributes", PropType::Int),
+];
+
+// Internet shortcuts and favorites (.url) and shell links (.lnk).
+// The decoded target attributes are added by `insert_shortcut_fields`.
+pub const SHORTCUT_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemPathDisplay", PropType::Str),
+    ("System.ItemType", PropType::Str),
+    ("System.Link.TargetUrl", PropType::Str),
+    ("System.Link.TargetParsingPath", PropType::Str),
+    ("System.Link.TargetExtension", PropType::StrList),
+    ("System.Link.Arguments", PropType::Str),
+    ("System.Link.Comment", PropType::Str),
+    ("System.Link.TargetSFGAOFlags", PropType::Int),
+    ("System.Link.DateVisited", PropType::DateTime),
+    ("System.DateCreated", PropType::DateTime),
+    ("System.DateModified", PropType::DateTime),
+    ("System.DateAccessed", PropType::DateTime),
+];
+
+// Indexed files and activities compared with the $MFT given with --mft.
+// The differences and the timestamps of the MFT entry are added by `write_mft_comparison`.
+pub const MFT_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemPathDisplay", PropType::Str),
+    ("System.FileFRN", PropType::Int),
+    ("System.DateCreated", PropType::DateTime),
+    ("System.DateModified", PropType::DateTime),
+    ("System.Activity.ContentUri", PropType::Str),
+];
+
+// Indexed applications: Start menu entries and packaged apps.
+// Their usage in Activity History is added when the database is done.
+pub const APPLICATION_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemNameDisplay", PropType::Str),
+    ("System.ItemPathDisplay", PropType::Str),
+    ("System.AppUserModel.ID", PropType::Str),
+    ("System.AppUserModel.PackageFamilyName", PropType::Str),
+    ("System.AppUserModel.PackageFullName", PropType::Str),
+    ("System.AppUserModel.PackageInstallPath", PropType::Str),
+    ("System.Link.TargetParsingPath", PropType::Str),
+    ("System.Software.ProductName", PropType::Str),
+    ("System.Software.ProductVersion", PropType::Str),
+    ("System.Software.DateLastUsed", PropType::DateTime),
+    ("System.DateCreated", PropType::DateTime),
+];
+
+// Activity History (Timeline). User, application, duration and ContentUri columns are added
+// by `insert_activity_fields`.
+pub const ACTIVITY_PROPERTIES: &[Property] = &[
+    ("System.ComputerName", PropType::Str),
+    ("System.ItemNameDisplay", PropType::Str),
+    ("System.ItemUrl", PropType::Str),
+    ("System.ActivityHistory.Id", PropType::Guid),
+    ("System.ActivityHistory.StartTime", PropType::DateTime),
+    ("System.ActivityHistory.EndTime", PropType::DateTime),
+    ("System.ActivityHistory.LocalStartTime", PropType::DateTime),
+    ("System.ActivityHistory.LocalEndTime", PropType::DateTime),
+    ("System.ActivityHistory.ActiveDuration", PropType::Int),
+    // days and hours since 1970-01-01
+    ("System.ActivityHistory.ActiveDays", PropType::StrList),
+    ("System.ActivityHistory.DaysActive", PropType::StrList),
+    ("System.ActivityHistory.HoursActive", PropType::StrList),
+    ("System.ActivityHistory.AppId", PropType::Str),
+    ("System.ActivityHistory.AppActivityId", PropType::Str),
+    ("System.ActivityHistory.Importance", PropType::Int),
+    ("System.ActivityHistory.IsLocal", PropType::Bool),
+    ("System.ActivityHistory.IsHistoryAttributedToSetAnchor", PropType::Bool),
+    ("System.ActivityHistory.LocationActivityId", PropType::Guid),
+    ("System.ActivityHistory.DeviceId", PropType::Str),
+    ("System.ActivityHistory.DeviceName", PropType::Str),
+    ("System.ActivityHistory.DeviceMake", PropType::Str),
+    ("System.ActivityHistory.DeviceModel", PropType::Str),
+    ("System.ActivityHistory.DeviceType", PropType::Str),
+    ("System.Activity.ActivityId", PropType::Guid),
+    ("System.Activity.AccountId", PropType::Str),
+    ("System.Activity.AppDisplayName", PropType::Str),
+    ("System.Activity.AppIdKind", PropType::Int),
+    ("System.Activity.AppIdList", PropType::StrList),
+    ("System.Activity.AppImageUri", PropType::Str),
+    ("System.Activity.AttributionName", PropType::Str),
+    ("System.Activity.DisplayText", PropType::Str),
+    ("System.Activity.Description", PropType::Str),
+    ("System.Activity.ContentUri", PropType::Str),
+    ("System.Activity.ContentImageUri", PropType::Str),
+    ("System.Activity.ActivationUri", PropType::Str),
+    ("System.Activity.FallbackUri", PropType::Str),
+    ("System.Activity.BackgroundColor", PropType::Int),
+    ("System.Activity.ContentVisualPropertiesHash", PropType::Int),
+    ("System.Activity.HasAdaptiveContent", PropType::Bool),
+    ("System.Activity.SetCategory", PropType::Str),
+    ("System.Activity.SetId", PropType::Str),
+];
+
+/// Columns of a report with the same layout for Windows.edb and Windows.db:
+/// WorkId, the properties, then the columns computed from them.
+pub struct ReportLayout {
+    pub properties: &'static [Property],
+    /// Named without dots (PinState), written by the code filling the report.
+    pub computed: &'static [Property],
+}
+
+impl ReportLayout {
+    /// Names of all columns, in order.
+    pub fn columns(&self) -> impl Iterator<Item = (String, PropType)> + '_ {
+        std::iter::once(("WorkId".to_string(), PropType::Int)).chain(
+            self.properties
+                .iter()
+                .chain(self.computed)
+                .map(|(name, typ)| (column_name(name), *typ)),
+        )
+    }
+}
+
+pub const ACTIVITY_REPORT: ReportLayout = ReportLayout {
+    properties: ACTIVITY_PROPERTIES,
+    computed: &[
+        ("DurationSeconds", PropType::Int),
+        ("UserSID", PropType::Str),
+        ("UserName", PropType::Str),
+        ("UserProfilePath", PropType::Str),
+        ("Application", PropType::Str),
+        ("ApplicationPlatform", PropType::Str),
+        ("VolumeId", PropType::Str),
+        ("ObjectId", PropType::Str),
+        ("ObjectIdTimestamp", PropType::DateTime),
+        ("ObjectIdClockSequence", PropType::Int),
+        ("ObjectIdMacAddress", PropType::Str),
+        ("KnownFolderId", PropType::Str),
+        ("KnownFolderLength", PropType::Int),
+        ("TimelineMatch", PropType::Str),
+        ("TimelineActivityType", PropType::Str),
+        ("TimelineActivityStatus", PropType::Int),
+        ("TimelineDisplayText", PropType::Str),
+        ("TimelineDescription", PropType::Str),
+        ("TimelineAppDisplayName", PropType::Str),
+        ("TimelineActiveDurationSeconds", PropType::Int),
+        ("TimelineStartTime", PropType::DateTime),
+        ("TimelineEndTime", PropType::DateTime),
+        ("TimelineLastModifiedTime", PropType::DateTime),
+        ("TimelineExpirationTime", PropType::DateTime),
+        ("TimelinePlatformDeviceId", PropType::Str),
+        ("TimelineIsLocalOnly", PropType::Bool),
+        ("TimelineDatabase", PropType::Str),
+    ],
+};
+pub const EMAIL_REPORT: ReportLayout = ReportLayout {
+    properties: EMAIL_PROPERTIES,
+    computed: &[],
+};
+pub const CONTACTS_REPORT: ReportLayout = ReportLayout {
+    properties: CONTACTS_PROPERTIES,
+    computed: &[],
+};
+pub const CALENDAR_REPORT: ReportLayout = ReportLayout {
+    properties: CALENDAR_PROPERTIES,
+    computed: &[],
+};
+pub const TASKS_REPORT: ReportLayout = ReportLayout {
+    properties: TASKS_PROPERTIES,
+    computed: &[],
+};
+pub const DOCUMENT_REPORT: ReportLayout = ReportLayout {
+    properties: DOCUMENT_PROPERTIES,
+    computed: &[],
+};
+pub const MEDIA_REPORT: ReportLayout = ReportLayout {
+    properties: MEDIA_PROPERTIES,
+    computed: &[
+        ("DurationSeconds", PropType::Float),
+        ("Latitude", PropType::Float),
+        ("Longitude", PropType::Float),
+    ],
+};
+pub const CLOUD_REPORT: ReportLayout = ReportLayout {
+    properties: CLOUD_PROPERTIES,
+    computed: &[
+        ("CloudProvider", PropType::Str),
+        ("CloudAccount", PropType::Str),
+        ("CloudUserSID", PropType::Str),
+        ("CloudUserName", PropType::Str),
+        ("CloudUserProfilePath", PropType::Str),
+        ("SharePoint", PropType::Bool),
+        ("ProviderState", PropType::Str),
+        ("SyncStatus", PropType::Str),
+        ("PlaceholderStatus", PropType::Str),
+        ("PinState", PropType::Str),
+    ],
+};
+pub const SHORTCUT_REPORT: ReportLayout = ReportLayout {
+    properties: SHORTCUT_PROPERTIES,
+    computed: &[("TargetAttributes", PropType::Str)],
+};
+pub const MFT_REPORT: ReportLayout = ReportLayout {
+    properties: MFT_PROPERTIES,
+    computed: &[
+        ("Finding", PropType::StrList),
+        ("ObjectId", PropType::Str),
+        ("MftEntry", PropType::Int),
+        ("MftSequence", PropType::Int),
+        ("MftInUse", PropType::Bool),
+        ("MftPath", PropType::Str),
+        ("SiCreated", PropType::DateTime),
+        ("SiModified", PropType::DateTime),
+        ("FnCreated", PropType::DateTime),
+        ("FnModified", PropType::DateTime),
+    ],
+};
+pub const APPLICATION_REPORT: ReportLayout = ReportLayout {
+    properties: APPLICATION_PROPERTIES,
+    computed: &[
+        ("Indexed", PropType::Bool),
+        ("UsageCount", PropType::Int),
+        ("FirstUsed", PropType::DateTime),
+        ("LastUsed", PropType::DateTime),
+    ],
+};
+
+/// Properties read by `gps_coordinate`.
+pub const GPS_PROPERTIES: &[&str] = &[
+    "System.GPS.Latitude",
+    "System.GPS.LatitudeRef",
+    "System.GPS.LatitudeDecimal",
+    "System.GPS.LatitudeNumerator",
+    "System.GPS.LatitudeDenominator",
+    "System.GPS.Longitude",
+    "System.GPS.LongitudeRef",
+    "System.GPS.LongitudeDecimal",
+    "System.GPS.LongitudeNumerator",
+    "System.GPS.LongitudeDenominator",
+];
+
+/// Decimal degrees of System.GPS.Latitude or System.GPS.Longitude (`axis` is "Latitude" or
+/// "Longitude"), from the decimal property if the indexer stored one, otherwise from the
+/// degrees, minutes and seconds rationals.
+pub fn gps_coordinate(rec: &dyn PropertyRecord, axis: &str) -> Option<f64> {
+    let reference = rec.get_str(&format!("System.GPS.{axis}Ref"));
+    if let Some(v) = rec.get_float(&format!("System.GPS.{axis}Decimal")) {
+        return Some(v);
+    }
+    let dms = match (
+        rec.get_bytes(&format!("System.GPS.{axis}Numerator")),
+        rec.get_bytes(&format!("System.GPS.{axis}Denominator")),
+    ) {
+        (Some(n), Some(d)) => gps_rationals(&n, &d),
+        // vector of doubles
+        _ => rec
+            .get_bytes(&format!("System.GPS.{axis}"))?
+            .chunks_exact(8)
+            .map(|v| f64::from_le_bytes(v.try_into().unwrap()))
+            .collect(),
+    };
+    gps_to_decimal(&dms, reference.as_deref())
+}
+
+#[test]
+fn test_split_utf16_list() {
+    let val: Vec<u8> = "link\0program\0"
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    assert_eq!(split_utf16_list(&val), vec!["link", "program"]);
+    assert!(split_utf16_list(&[]).is_empty());
+}
+
+#[test]
+fn test_property_resolver() {
+    let ese = PropertyResolver::new([
+        ("4450-System_ItemType", "4450-System_ItemType"),
+        ("33-System_ItemUrl", "33-System_ItemUrl"),
+        ("WorkID", "WorkID"),
+    ]);
+    assert_eq!(ese.resolve("System.ItemType"), Some(&"4450-System_ItemType"));
+    assert_eq!(ese.resolve("System_ItemUrl"), Some(&"33-System_ItemUrl"));
+    assert_eq!(ese.resolve("WorkID"), Some(&"WorkID"));
+    assert_eq!(ese.resolve("System.ComputerName"), None);
+
+    let sqlite = PropertyResolver::new([("System.ItemType", 567), ("System.Activity.AppId", 42)]);
+    assert_eq!(sqlite.resolve("System.ItemType"), Some(&567));
+    assert_eq!(sqlite.resolve("System_Activity_AppId"), Some(&42));
+    assert_eq!(sqlite.resolve("System.ItemUrl"), None);
+}
@@ -0,0 +1,333 @@
+use simple_error::SimpleError;
+use std::convert::TryInto;
+use std::path::Path;
+
+use crate::utils::*;
+
+// Minimal read-only parser for offline registry hives (regf),
+// enough to walk keys and read values of SOFTWARE and SAM.
+
+const HBIN_START: usize = 0x1000;
+const KEY_COMP_NAME: u16 = 0x20;
+const VALUE_COMP_NAME: u16 = 0x1;
+const DATA_INLINE: u32 = 0x80000000;
+
+pub const REG_SZ: u32 = 1;
+pub const REG_EXPAND_SZ: u32 = 2;
+
+pub struct RegHive {
+    data: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+pub struct RegKey<'a> {
+    hive: &'a RegHive,
+    // offset of the nk cell data in the file
+    offset: usize,
+}
+
+pub struct RegValue {
+    pub name: String,
+    pub typ: u32,
+    pub data: Vec<u8>,
+}
+
+impl RegHive {
+    pub fn open(path: &Path) -> Result<Self, SimpleError> {
+        let data = std::fs::read(path).map_err(|e| {
+            SimpleError::new(format!("Can't read hive '{}': {e}", path.to_string_lossy()))
+        })?;
+        Self::from_bytes(data)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, SimpleError> {
+        if data.len() < HBIN_START || &data[0..4] != b"regf" {
+            return Err(SimpleError::new("Not a registry hive (missing regf signature)"));
+        }
+        Ok(RegHive { data })
+    }
+
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        Some(u16::from_le_bytes(
+            self.data.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        Some(u32::from_le_bytes(
+            self.data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    }
+
+    // Cell offsets are relative to the first hbin; the cell starts with its (negative) size.
+    fn cell(&self, cell_offset: u32) -> Option<&[u8]> {
+        let start = HBIN_START + cell_offset as usize;
+        let size = i32::from_le_bytes(self.data.get(start..start + 4)?.try_into().ok()?);
+        let size = size.unsigned_abs() as usize;
+        if size < 4 {
+            return None;
+        }
+        self.data.get(start + 4..start + size)
+    }
+
+    fn key_at(&self, cell_offset: u32) -> Option<RegKey<'_>> {
+        let cell = self.cell(cell_offset)?;
+        if cell.len() < 0x4C || &cell[0..2] != b"nk" {
+            return None;
+        }
+        Some(RegKey {
+            hive: self,
+            offset: HBIN_START + cell_offset as usize + 4,
+        })
+    }
+
+    pub fn root(&self) -> Result<RegKey<'_>, SimpleError> {
+        let root_offset = self
+            .u32_at(0x24)
+            .ok_or_else(|| SimpleError::new("Truncated hive header"))?;
+        self.key_at(root_offset)
+            .ok_or_else(|| SimpleError::new("Can't find the root key of the hive"))
+    }
+
+    // Walks lf/lh/li/ri subkey lists, ri lists point to other lists.
+    fn collect_subkeys(&self, list_offset: u32, out: &mut Vec<u32>, depth: usize) {
+        let cell = match self.cell(list_offset) {
+            Some(c) if c.len() >= 4 && depth < 8 => c,
+            _ => return,
+        };
+        let count = u16::from_le_bytes([cell[2], cell[3]]) as usize;
+        let (stride, nested) = match &cell[0..2] {
+            b"lf" | b"lh" => (8, false),
+            b"li" => (4, false),
+            b"ri" => (4, true),
+            _ => return,
+        };
+        for i in 0..count {
+            let pos = 4 + i * stride;
+            if let Some(bytes) = cell.get(pos..pos + 4) {
+                let offset = u32::from_le_bytes(bytes.try_into().unwrap());
+                if nested {
+                    self.collect_subkeys(offset, out, depth + 1);
+                } else {
+                    out.push(offset);
+                }
+            }
+        }
+    }
+}
+
+impl<'a> RegKey<'a> {
+    pub fn name(&self) -> String {
+        let hive = self.hive;
+        let flags = hive.u16_at(self.offset + 0x02).unwrap_or(0);
+        let len = hive.u16_at(self.offset + 0x48).unwrap_or(0) as usize;
+        let start = self.offset + 0x4C;
+        match hive.data.get(start..start + len) {
+            Some(name) if flags & KEY_COMP_NAME != 0 => String::from_utf8_lossy(name).into_owned(),
+            Some(name) => from_utf16(name),
+            None => String::new(),
+        }
+    }
+
+    pub fn subkeys(&self) -> Vec<RegKey<'a>> {
+        let hive = self.hive;
+        let count = hive.u32_at(self.offset + 0x14).unwrap_or(0);
+        let mut offsets = Vec::new();
+        if count > 0 {
+            if let Some(list) = hive.u32_at(self.offset + 0x1C) {
+                hive.collect_subkeys(list, &mut offsets, 0);
+            }
+        }
+        offsets.into_iter().filter_map(|o| hive.key_at(o)).collect()
+    }
+
+    pub fn subkey(&self, name: &str) -> Option<RegKey<'a>> {
+        self.subkeys()
+            .into_iter()
+            .find(|k| k.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Follows a backslash-separated path below this key, names are case-insensitive.
+    pub fn subkey_path(&self, path: &str) -> Option<RegKey<'a>> {
+        let mut key = *self;
+        for part in path.split('\\').filter(|p| !p.is_empty()) {
+            key = key.subkey(part)?;
+        }
+        Some(key)
+    }
+
+    pub fn values(&self) -> Vec<RegValue> {
+        let hive = self.hive;
+        let count = hive.u32_at(self.offset + 0x24).unwrap_or(0) as usize;
+        let list = match hive.u32_at(self.offset + 0x28) {
+            Some(l) if count > 0 => l,
+            _ => return Vec::new(),
+        };
+        let list = match hive.cell(list) {
+            Some(c) => c,
+            None => return Vec::new(),
+        };
+        (0..count)
+            .filter_map(|i| list.get(i * 4..i * 4 + 4))
+            .filter_map(|b| self.value_at(u32::from_le_bytes(b.try_into().unwrap())))
+            .collect()
+    }
+
+    /// Value by name, the default value has an empty name.
+    pub fn value(&self, name: &str) -> Option<RegValue> {
+        self.values()
+            .into_iter()
+            .find(|v| v.name.eq_ignore_ascii_case(name))
+    }
+
+    fn value_at(&self, cell_offset: u32) -> Option<RegValue> {
+        let hive = self.hive;
+        let vk = hive.cell(cell_offset)?;
+        if vk.len() < 0x14 || &vk[0..2] != b"vk" {
+            return None;
+        }
+        let name_len = u16::from_le_bytes([vk[2], vk[3]]) as usize;
+        let size = u32::from_le_bytes(vk[4..8].try_into().unwrap());
+        let data_offset = u32::from_le_bytes(vk[8..12].try_into().unwrap());
+        let typ = u32::from_le_bytes(vk[12..16].try_into().unwrap());
+        let flags = u16::from_le_bytes([vk[16], vk[17]]);
+        let name = match vk.get(0x14..0x14 + name_len) {
+            Some(n) if flags & VALUE_COMP_NAME != 0 => String::from_utf8_lossy(n).into_owned(),
+            Some(n) => from_utf16(n),
+            None => String::new(),
+        };
+        let data = if size & DATA_INLINE != 0 {
+            let len = ((size & !DATA_INLINE) as usize).min(4);
+            vk[8..8 + len].to_vec()
+        } else {
+            let cell = hive.cell(data_offset)?;
+            let len = (size as usize).min(cell.len());
+            cell[..len].to_vec()
+        };
+        Some(RegValue { name, typ, data })
+    }
+}
+
+impl RegValue {
+    /// REG_SZ/REG_EXPAND_SZ as a string, without the terminating null.
+    pub fn as_string(&self) -> String {
+        from_utf16(&self.data).trim_end_matches('\0').to_string()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    // Builds a hive in memory: every cell gets appended to a single hbin.
+    #[derive(Default)]
+    pub struct HiveBuilder {
+        cells: Vec<u8>,
+    }
+
+    impl HiveBuilder {
+        fn add_cell(&mut self, data: &[u8]) -> u32 {
+            // hbin header is 0x20 bytes
+            let offset = 0x20 + self.cells.len() as u32;
+            let size = (data.len() + 4 + 7) & !7;
+            self.cells
+                .extend_from_slice(&(-(size as i32)).to_le_bytes());
+            self.cells.extend_from_slice(data);
+            self.cells.resize(self.cells.len() + size - 4 - data.len(), 0);
+            offset
+        }
+
+        pub fn add_value(&mut self, name: &str, typ: u32, data: &[u8]) -> u32 {
+            let data_offset = if data.len() <= 4 {
+                let mut inline = [0u8; 4];
+                inline[..data.len()].copy_from_slice(data);
+                u32::from_le_bytes(inline)
+            } else {
+                self.add_cell(data)
+            };
+            let size = if data.len() <= 4 {
+                data.len() as u32 | super::DATA_INLINE
+            } else {
+                data.len() as u32
+            };
+            let mut vk = b"vk".to_vec();
+            vk.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            vk.extend_from_slice(&size.to_le_bytes());
+            vk.extend_from_slice(&data_offset.to_le_bytes());
+            vk.extend_from_slice(&typ.to_le_bytes());
+            vk.extend_from_slice(&super::VALUE_COMP_NAME.to_le_bytes());
+            vk.extend_from_slice(&[0, 0]);
+            vk.extend_from_slice(name.as_bytes());
+            self.add_cell(&vk)
+        }
+
+        pub fn add_key(&mut self, name: &str, subkeys: &[u32], values: &[u32]) -> u32 {
+            let subkey_list = if subkeys.is_empty() {
+                u32::MAX
+            } else {
+                let mut lf = b"lf".to_vec();
+                lf.extend_from_slice(&(subkeys.len() as u16).to_le_bytes());
+                for k in subkeys {
+                    lf.extend_from_slice(&k.to_le_bytes());
+                    lf.extend_from_slice(&[0; 4]);
+                }
+                self.add_cell(&lf)
+            };
+            let value_list = if values.is_empty() {
+                u32::MAX
+            } else {
+                let list: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                self.add_cell(&list)
+            };
+            let mut nk = vec![0u8; 0x4C];
+            nk[0..2].copy_from_slice(b"nk");
+            nk[2..4].copy_from_slice(&super::KEY_COMP_NAME.to_le_bytes());
+            nk[0x14..0x18].copy_from_slice(&(subkeys.len() as u32).to_le_bytes());
+            nk[0x1C..0x20].copy_from_slice(&subkey_list.to_le_bytes());
+            nk[0x24..0x28].copy_from_slice(&(values.len() as u32).to_le_bytes());
+            nk[0x28..0x2C].copy_from_slice(&value_list.to_le_bytes());
+            nk[0x48..0x4A].copy_from_slice(&(name.len() as u16).to_le_bytes());
+            nk.extend_from_slice(name.as_bytes());
+            self.add_cell(&nk)
+        }
+
+        pub fn build(self, root: u32) -> Vec<u8> {
+            let mut data = vec![0u8; super::HBIN_START];
+            data[0..4].copy_from_slice(b"regf");
+            data[0x24..0x28].copy_from_slice(&root.to_le_bytes());
+            let mut hbin = vec![0u8; 0x20];
+            hbin[0..4].copy_from_slice(b"hbin");
+            data.extend_from_slice(&hbin);
+            data.extend_from_slice(&self.cells);
+            data
+        }
+    }
+
+    pub fn utf16z(s: &str) -> Vec<u8> {
+        s.encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(|c| c.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_read_hive() {
+        use super::*;
+        let mut b = HiveBuilder::default();
+        let path = b.add_value("ProfileImagePath", REG_EXPAND_SZ, &utf16z("C:\\Users\\alice"));
+        let flags = b.add_value("Flags", 4, &[1, 0, 0, 0]);
+        let sid = b.add_key("S-1-5-21-1-2-3-1001", &[], &[path, flags]);
+        let list = b.add_key("ProfileList", &[sid], &[]);
+        let root = b.add_key("ROOT", &[list], &[]);
+        let hive = RegHive::from_bytes(b.build(root)).unwrap();
+
+        let root = hive.root().unwrap();
+        assert_eq!(root.name(), "ROOT");
+        let key = root.subkey_path("profilelist\\S-1-5-21-1-2-3-1001").unwrap();
+        assert_eq!(
+            key.value("ProfileImagePath").unwrap().as_string(),
+            "C:\\Users\\alice"
+        );
+        assert_eq!(key.value("Flags").unwrap().data, vec![1, 0, 0, 0]);
+        assert!(root.subkey_path("ProfileList\\S-1-5-18").is_none());
+        assert!(RegHive::from_bytes(vec![0; 0x2000]).is_err());
+    }
+}
@@ -32,6 +32,16 @@ pub enum ReportSuffix {
     FileReport,
     ActivityHistory,
     InternetHistory,
+    Email,
+    Contacts,
+    Calendar,
+    Tasks,
+    DocumentMetadata,
+    Media,
+    CloudFiles,
+    Shortcuts,
+    Applications,
+    MftComparison,
     Unknown,
 }
 
@@ -41,6 +51,16 @@ impl ReportSuffix {
             "File_Report" => Some(ReportSuffix::FileReport),
             "Activity_History_Report" => Some(ReportSuffix::ActivityHistory),
             "Internet_History_Report" => Some(ReportSuffix::InternetHistory),
+            "Email_Report" => Some(ReportSuffix::Email),
+            "Contacts_Report" => Some(ReportSuffix::Contacts),
+            "Calendar_Report" => Some(ReportSuffix::Calendar),
+            "Tasks_Report" => Some(ReportSuffix::Tasks),
+            "Document_Metadata_Report" => Some(ReportSuffix::DocumentMetadata),
+            "Media_Report" => Some(ReportSuffix::Media),
+            "Cloud_Files_Report" => Some(ReportSuffix::CloudFiles),
+            "Shortcuts_Report" => Some(ReportSuffix::Shortcuts),
+            "Applications_Report" => Some(ReportSuffix::Applications),
+            "MFT_Comparison_Report" => Some(ReportSuffix::MftComparison),
             &_ => Some(ReportSuffix::Unknown),
         }
     }
@@ -53,6 +73,16 @@ impl ReportSuffix {
             Self::FileReport => serde_json::to_string("file_report").unwrap(),
             Self::ActivityHistory => serde_json::to_string("activity_history").unwrap(),
             Self::InternetHistory => serde_json::to_string("internet_history").unwrap(),
+            Self::Email => serde_json::to_string("email").unwrap(),
+            Self::Contacts => serde_json::to_string("contacts").unwrap(),
+            Self::Calendar => serde_json::to_string("calendar").unwrap(),
+            Self::Tasks => serde_json::to_string("tasks").unwrap(),
+            Self::DocumentMetadata => serde_json::to_string("document_metadata").unwrap(),
+            Self::Media => serde_json::to_string("media").unwrap(),
+            Self::CloudFiles => serde_json::to_string("cloud_files").unwrap(),
+            Self::Shortcuts => serde_json::to_string("shortcuts").unwrap(),
+            Self::Applications => serde_json::to_string("applications").unwrap(),
+            Self::MftComparison => serde_json::to_string("mft_comparison").unwrap(),
             Self::Unknown => serde_json::to_string("").unwrap(),
         }
     }
@@ -70,6 +100,8 @@ pub struct ReportProducer {
     report_type: ReportOutput,
     instance: Option<String>,
     database: Option<String>,
+    split_by_hostname: bool,
+    geojson: bool,
 }
 
 impl ReportProducer {
@@ -84,6 +116,8 @@ impl ReportProducer {
             report_type,
             instance: instance,
             database: database,
+            split_by_hostname: false,
+            geojson: false,
         }
     }
 
@@ -91,6 +125,26 @@ impl ReportProducer {
         self.report_type
     }
 
+    /// Write separate reports for every System_ComputerName found in a database
+    /// instead of naming all of them after the hostname recovered for the database.
+    pub fn set_split_by_hostname(&mut self, split: bool) {
+        self.split_by_hostname = split;
+    }
+
+    pub fn split_by_hostname(&self) -> bool {
+        self.split_by_hostname
+    }
+
+    /// Also write the locations of geotagged media as a GeoJSON feature collection
+    /// (HOSTNAME_Media_Locations_DateTime.geojson).
+    pub fn set_geojson(&mut self, geojson: bool) {
+        self.geojson = geojson;
+    }
+
+    pub fn geojson(&self) -> bool {
+        self.geojson
+    }
+
     pub fn is_db_dirty(&self, db_state: Option<DbState>) -> bool {
         match db_state {
             Some(state) => state != DbState::CleanShutdown,
@@ -192,11 +246,17 @@ impl ReportProducer {
     }
 }
 
+pub const LIST_SEPARATOR: &str = "; ";
+
 pub trait Report {
     fn footer(&mut self) {}
     fn create_new_row(&mut self, f: bool);
     fn insert_str_val(&self, f: &str, s: String);
     fn insert_int_val(&self, f: &str, n: u64);
+    // all values of a multi-valued property; a JSON array, joined with LIST_SEPARATOR elsewhere
+    fn insert_str_list_val(&self, f: &str, values: Vec<String>) {
+        self.insert_str_val(f, values.join(LIST_SEPARATOR));
+    }
     fn set_field(&self, _: &str) {} // used in csv to generate header
     fn start_file(&mut self) {}
     fn end_file(&mut self) {}
@@ -321,6 +381,13 @@ impl Report for ReportJson {
         self.values.borrow_mut().push(format!("\"{f}\":{n}"));
     }
 
+    fn insert_str_list_val(&self, f: &str, values: Vec<String>) {
+        let values: Vec<String> = values.into_iter().map(ReportJson::escape).collect();
+        self.values
+            .borrow_mut()
+            .push(format!("\"{}\":[{}]", f, values.join(",")));
+    }
+
     fn is_some_val_in_record(&self) -> bool {
         !self.values.borrow().is_empty()
     }
@@ -484,10 +551,109 @@ impl Drop for ReportCsv {
     }
 }
 
+// report decorator, adds the source of the database to every row
+pub struct ReportWithSource {
+    rep: Box<dyn Report>,
+    source: String,
+}
+
+impl ReportWithSource {
+    pub fn new(rep: Box<dyn Report>, source: &str) -> Self {
+        ReportWithSource {
+            rep,
+            source: source.to_string(),
+        }
+    }
+}
+
+impl Report for ReportWithSource {
+    fn footer(&mut self) {
+        self.rep.footer()
+    }
+
+    fn create_new_row(&mut self, f: bool) {
+        self.rep.create_new_row(f);
+        self.rep.insert_str_val("SourceArchive", self.source.clone());
+    }
+
+    fn insert_str_val(&self, f: &str, s: String) {
+        self.rep.insert_str_val(f, s)
+    }
+
+    fn insert_int_val(&self, f: &str, n: u64) {
+        self.rep.insert_int_val(f, n)
+    }
+
+    fn insert_str_list_val(&self, f: &str, values: Vec<String>) {
+        self.rep.insert_str_list_val(f, values)
+    }
+
+    fn set_field(&self, f: &str) {
+        self.rep.set_field(f)
+    }
+
+    fn is_some_val_in_record(&self) -> bool {
+        self.rep.is_some_val_in_record()
+    }
+}
+
+// report decorator, adds the user a per-user database belongs to to every row
+pub struct ReportWithDbOwner {
+    rep: Box<dyn Report>,
+    sid: String,
+    name: Option<String>,
+}
+
+impl ReportWithDbOwner {
+    /// `name` is None when no SIDs were loaded, the DbOwnerName column is not written then.
+    pub fn new(rep: Box<dyn Report>, sid: &str, name: Option<String>) -> Self {
+        ReportWithDbOwner {
+            rep,
+            sid: sid.to_string(),
+            name,
+        }
+    }
+}
+
+impl Report for ReportWithDbOwner {
+    fn footer(&mut self) {
+        self.rep.footer()
+    }
+
+    fn create_new_row(&mut self, f: bool) {
+        self.rep.create_new_row(f);
+        self.rep.insert_str_val("DbOwnerSID", self.sid.clone());
+        if let Some(name) = &self.name {
+            self.rep.insert_str_val("DbOwnerName", name.clone());
+        }
+    }
+
+    fn insert_str_val(&self, f: &str, s: String) {
+        self.rep.insert_str_val(f, s)
+    }
+
+    fn insert_int_val(&self, f: &str, n: u64) {
+        self.rep.insert_int_val(f, n)
+    }
+
+    fn insert_str_list_val(&self, f: &str, values: Vec<String>) {
+        self.rep.insert_str_list_val(f, values)
+    }
+
+    fn set_field(&self, f: &str) {
+        self.rep.set_field(f)
+    }
+
+    fn is_some_val_in_record(&self) -> bool {
+        self.rep.is_some_val_in_record()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::report::{
         Report, ReportCsv, ReportFormat, ReportJson, ReportOutput, ReportProducer, ReportSuffix,
+        ReportWithDbOwner, ReportWithSource,
     };
     use chrono::{DateTime, NaiveDate, Utc};
     use ese_parser_lib::parser::jet::DbState;
@@ -529,6 +695,49 @@ mod tests {
         std::fs::remove_file(p).unwrap();
     }
 
+    #[test]
+    pub fn test_report_with_source() {
+        let p = Path::new("test_source.csv");
+        {
+            let csv = ReportCsv::new(p, ReportOutput::ToFile, None).unwrap();
+            let mut r = ReportWithSource::new(Box::new(csv), "kape.zip/C/Windows.db");
+            for i in 0..2 {
+                r.create_new_row(false);
+                r.insert_int_val("WorkId", i);
+            }
+        }
+        let data = std::fs::read_to_string(p).unwrap();
+        let expected = r#"SourceArchive,WorkId
+"kape.zip/C/Windows.db",0
+"kape.zip/C/Windows.db",1"#;
+        assert_eq!(data, expected);
+        std::fs::remove_file(p).unwrap();
+    }
+
+    #[test]
+    pub fn test_report_with_db_owner() {
+        let p = Path::new("test_db_owner.csv");
+        {
+            let csv = ReportCsv::new(p, ReportOutput::ToFile, None).unwrap();
+            let csv = ReportWithSource::new(Box::new(csv), "kape.zip/C/S-1-5-21-1-2-3-1001.db");
+            let mut r = ReportWithDbOwner::new(
+                Box::new(csv),
+                "S-1-5-21-1-2-3-1001",
+                Some("alice".to_string()),
+            );
+            for i in 0..2 {
+                r.create_new_row(false);
+                r.insert_int_val("WorkId", i);
+            }
+        }
+        let data = std::fs::read_to_string(p).unwrap();
+        let expected = r#"SourceArchive,DbOwnerSID,DbOwnerName,WorkId
+"kape.zip/C/S-1-5-21-1-2-3-1001.db","S-1-5-21-1-2-3-1001","alice",0
+"kape.zip/C/S-1-5-21-1-2-3-1001.db","S-1-5-21-1-2-3-1001","alice",1"#;
+        assert_eq!(data, expected);
+        std::fs::remove_file(p).unwrap();
+    }
+
     #[test]
     pub fn test_report_jsonl() {
         let p = Path::new("test.json");
@@ -548,7 +757,8 @@ mod tests {
             }
         }
         let data = std::fs::read_to_string(p).unwrap();
-        let expected = r#"{"int_field":0,"str_field":"string0_with_escapes_here1\"here2\\"},
+        let expected = r#"[
+{"int_field":0,"str_field":"string0_with_escapes_here1\"here2\\"},
 {"int_field":1},
 {"str_field":"string2"},
 {"int_field":3},
@@ -563,6 +773,30 @@ mod tests {
         std::fs::remove_file(p).unwrap();
     }
 
+    #[test]
+    pub fn test_report_str_list() {
+        let authors = vec!["Alice".to_string(), "Bob \"B\"".to_string()];
+        let p = Path::new("test_str_list.json");
+        {
+            let r = ReportJson::new(p, ReportOutput::ToFile, None).unwrap();
+            r.insert_int_val("WorkId", 1);
+            r.insert_str_list_val("System_Author", authors.clone());
+        }
+        let data = std::fs::read_to_string(p).unwrap();
+        assert_eq!(data, "[\n{\"WorkId\":1,\"System_Author\":[\"Alice\",\"Bob \\\"B\\\"\"]}\n]");
+        std::fs::remove_file(p).unwrap();
+
+        let p = Path::new("test_str_list.csv");
+        {
+            let r = ReportCsv::new(p, ReportOutput::ToFile, None).unwrap();
+            r.insert_int_val("WorkId", 1);
+            r.insert_str_list_val("System_Author", authors);
+        }
+        let data = std::fs::read_to_string(p).unwrap();
+        assert_eq!(data, "WorkId,System_Author\n1,\"Alice; Bob \"\"B\"\"\"");
+        std::fs::remove_file(p).unwrap();
+    }
+
     #[test]
     fn test_report_suffix() {
         let report_suffix = Some(ReportSuffix::FileReport);
@@ -590,7 +824,7 @@ mod tests {
     #[test]
     fn test_get_path_db_status() {
         let path = Path::new("./tests");
-        let rp = ReportProducer::new(path, ReportFormat::Json, ReportOutput::ToStdout);
+        let rp = ReportProducer::new(path, ReportFormat::Json, ReportOutput::ToStdout, None, None);
         let naivedatetime_utc = NaiveDate::from_ymd_opt(2000, 1, 12)
             .unwrap()
             .and_hms_opt(2, 0, 0)
@@ -627,7 +861,7 @@ mod tests {
     #[test]
     fn test_is_db_dirty() {
         let path = Path::new("./tests");
-        let rp = ReportProducer::new(path, ReportFormat::Json, ReportOutput::ToStdout);
+        let rp = ReportProducer::new(path, ReportFormat::Json, ReportOutput::ToStdout, None, None);
         assert_eq!(rp.is_db_dirty(Some(DbState::CleanShutdown)), false);
         assert_eq!(rp.is_db_dirty(Some(DbState::DirtyShutdown)), true);
         assert_eq!(rp.is_db_dirty(Some(DbState::BeingConverted)), true);
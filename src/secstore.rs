@@ -0,0 +1,352 @@
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::sids::*;
+
+// Security descriptors of the indexed items, keyed by the SDID of the gather table:
+// <database dir>/Projects/SystemIndex/SecStore.
+//
+// Only the descriptors themselves are documented: self-relative SECURITY_DESCRIPTOR,
+// MS-DTYP 2.4.6, with the SIDs, ACLs and ACEs of 2.4.2, 2.4.5 and 2.4.4. Microsoft does not
+// document the SecStore files, and the record framing assumed here (SDID and size as
+// little-endian u32 before each descriptor) has not been checked against a collected
+// SecStore. To not depend on it more than needed, records are found by looking for a valid
+// descriptor after such a header at every offset, and a SecStore where none is found is
+// reported by load_secstore.
+
+const SD_HEADER_LEN: usize = 20;
+const ACL_HEADER_LEN: usize = 8;
+const SE_DACL_PRESENT: u16 = 0x0004;
+const SE_SELF_RELATIVE: u16 = 0x8000;
+const INHERITED_ACE: u8 = 0x10;
+
+const ACCESS_ALLOWED_ACE_TYPE: u8 = 0x00;
+const ACCESS_DENIED_ACE_TYPE: u8 = 0x01;
+const ACCESS_ALLOWED_OBJECT_ACE_TYPE: u8 = 0x05;
+const ACCESS_DENIED_OBJECT_ACE_TYPE: u8 = 0x06;
+
+// Well-known SIDs of the ACLs of user files, named when the SID is not resolved otherwise.
+const WELL_KNOWN_SIDS: &[(&str, &str)] = &[
+    ("S-1-1-0", "Everyone"),
+    ("S-1-3-0", "CREATOR OWNER"),
+    ("S-1-3-1", "CREATOR GROUP"),
+    ("S-1-5-11", "Authenticated Users"),
+    ("S-1-5-18", "SYSTEM"),
+    ("S-1-5-19", "LOCAL SERVICE"),
+    ("S-1-5-20", "NETWORK SERVICE"),
+    ("S-1-5-32-544", "Administrators"),
+    ("S-1-5-32-545", "Users"),
+    ("S-1-15-2-1", "ALL APPLICATION PACKAGES"),
+];
+
+// File access rights, from the broadest; a mask is named after the first one it contains.
+const ACCESS_RIGHTS: &[(u32, &str)] = &[
+    (0x001F01FF, "FullControl"),
+    (0x10000000, "FullControl"),
+    (0x001301BF, "Modify"),
+    (0x001200A9, "ReadAndExecute"),
+    (0xA0000000, "ReadAndExecute"),
+    (0x00120089, "Read"),
+    (0x80000000, "Read"),
+    (0x00100116, "Write"),
+    (0x40000000, "Write"),
+];
+
+/// An access control entry of a DACL.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ace {
+    pub ace_type: u8,
+    pub flags: u8,
+    pub mask: u32,
+    pub sid: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SecurityDescriptor {
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    /// None when the descriptor has no DACL, which grants everyone full access.
+    pub dacl: Option<Vec<Ace>>,
+}
+
+fn read_u16(b: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(b.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(b: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(b.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn sid_at(b: &[u8], offset: u32) -> Option<Option<String>> {
+    match offset as usize {
+        0 => Some(None),
+        o => Some(Some(parse_sid(b.get(o..)?)?.0)),
+    }
+}
+
+fn parse_ace(b: &[u8]) -> Option<Ace> {
+    let ace_type = b[0];
+    let flags = b[1];
+    let mask = read_u32(b, 4)?;
+    let sid_pos = match ace_type {
+        ACCESS_ALLOWED_OBJECT_ACE_TYPE | ACCESS_DENIED_OBJECT_ACE_TYPE => {
+            // ObjectType and InheritedObjectType GUIDs are present when their flag is set
+            let object_flags = read_u32(b, 8)?;
+            12 + (object_flags & 1) as usize * 16 + (object_flags >> 1 & 1) as usize * 16
+        }
+        _ => 8,
+    };
+    let (sid, _) = parse_sid(b.get(sid_pos..)?)?;
+    Some(Ace {
+        ace_type,
+        flags,
+        mask,
+        sid,
+    })
+}
+
+fn parse_acl(b: &[u8]) -> Option<Vec<Ace>> {
+    if !matches!(b.first()?, 2 | 4) {
+        return None;
+    }
+    let size = read_u16(b, 2)? as usize;
+    let count = read_u16(b, 4)?;
+    let acl = b.get(..size)?;
+    let mut pos = ACL_HEADER_LEN;
+    let mut aces = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let ace_size = read_u16(acl, pos + 2)? as usize;
+        if ace_size < 8 {
+            return None;
+        }
+        let ace = acl.get(pos..pos + ace_size)?;
+        // other ACE types (callback, mandatory label...) are not summarized
+        if matches!(
+            ace[0],
+            ACCESS_ALLOWED_ACE_TYPE
+                | ACCESS_DENIED_ACE_TYPE
+                | ACCESS_ALLOWED_OBJECT_ACE_TYPE
+                | ACCESS_DENIED_OBJECT_ACE_TYPE
+        ) {
+            aces.push(parse_ace(ace)?);
+        }
+        pos += ace_size;
+    }
+    Some(aces)
+}
+
+/// Parses a self-relative SECURITY_DESCRIPTOR, None when it is anything else.
+pub fn parse_security_descriptor(b: &[u8]) -> Option<SecurityDescriptor> {
+    if b.len() < SD_HEADER_LEN || b[0] != 1 || b[1] != 0 {
+        return None;
+    }
+    let control = read_u16(b, 2)?;
+    if control & SE_SELF_RELATIVE == 0 {
+        return None;
+    }
+    let owner = sid_at(b, read_u32(b, 4)?)?;
+    let group = sid_at(b, read_u32(b, 8)?)?;
+    let dacl_offset = read_u32(b, 16)? as usize;
+    let dacl = if control & SE_DACL_PRESENT != 0 && dacl_offset != 0 {
+        Some(parse_acl(b.get(dacl_offset..)?)?)
+    } else {
+        None
+    };
+    if owner.is_none() && dacl.is_none() {
+        return None;
+    }
+    Some(SecurityDescriptor { owner, group, dacl })
+}
+
+fn access_rights(mask: u32) -> String {
+    ACCESS_RIGHTS
+        .iter()
+        .find(|(rights, _)| mask & rights == *rights)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("0x{mask:08X}"))
+}
+
+fn sid_name(sid: &str, sids: &SidResolver) -> String {
+    sids.resolve(sid)
+        .and_then(|u| u.name.clone())
+        .or_else(|| {
+            WELL_KNOWN_SIDS
+                .iter()
+                .find(|(s, _)| s.eq_ignore_ascii_case(sid))
+                .map(|(_, name)| name.to_string())
+        })
+        .unwrap_or_else(|| sid.to_string())
+}
+
+impl Ace {
+    /// `Allow Administrators: FullControl`, with `(inherited)` for inherited entries.
+    pub fn summary(&self, sids: &SidResolver) -> String {
+        let access = match self.ace_type {
+            ACCESS_DENIED_ACE_TYPE | ACCESS_DENIED_OBJECT_ACE_TYPE => "Deny",
+            _ => "Allow",
+        };
+        let inherited = if self.flags & INHERITED_ACE != 0 {
+            " (inherited)"
+        } else {
+            ""
+        };
+        format!(
+            "{access} {}: {}{inherited}",
+            sid_name(&self.sid, sids),
+            access_rights(self.mask)
+        )
+    }
+}
+
+impl SecurityDescriptor {
+    /// One entry per ACE of the DACL.
+    pub fn acl_summary(&self, sids: &SidResolver) -> Vec<String> {
+        match &self.dacl {
+            Some(aces) => aces.iter().map(|ace| ace.summary(sids)).collect(),
+            None => vec!["No DACL (Everyone: FullControl)".to_string()],
+        }
+    }
+}
+
+/// Security descriptors of a SecStore, by SDID.
+#[derive(Default)]
+pub struct SecStore {
+    descriptors: HashMap<u32, SecurityDescriptor>,
+}
+
+impl SecStore {
+    /// The SecStore of the database `db`, if it was collected along with it.
+    pub fn find(db: &Path) -> Option<PathBuf> {
+        let dir = db
+            .parent()?
+            .join("Projects")
+            .join("SystemIndex")
+            .join("SecStore");
+        dir.is_dir().then_some(dir)
+    }
+
+    /// Reads the security descriptors of every file of a SecStore directory.
+    pub fn load(dir: &Path) -> Result<Self, SimpleError> {
+        let mut store = SecStore::default();
+        let entries = fs::read_dir(dir).map_err(|e| {
+            SimpleError::new(format!(
+                "Can't read SecStore '{}': {e}",
+                dir.to_string_lossy()
+            ))
+        })?;
+        for entry in entries.flatten() {
+            if let Ok(data) = fs::read(entry.path()) {
+                store.add_records(&data);
+            }
+        }
+        Ok(store)
+    }
+
+    fn add_records(&mut self, data: &[u8]) {
+        let mut pos = 0;
+        while pos + 8 + SD_HEADER_LEN <= data.len() {
+            let sdid = read_u32(data, pos).unwrap_or_default();
+            let size = read_u32(data, pos + 4).unwrap_or_default() as usize;
+            let sd = data
+                .get(pos + 8..(pos + 8).saturating_add(size))
+                .filter(|_| sdid != 0 && size >= SD_HEADER_LEN)
+                .and_then(parse_security_descriptor);
+            match sd {
+                Some(sd) => {
+                    self.descriptors.entry(sdid).or_insert(sd);
+                    pos += 8 + size;
+                }
+                None => pos += 1,
+            }
+        }
+    }
+
+    pub fn get(&self, sdid: u32) -> Option<&SecurityDescriptor> {
+        self.descriptors.get(&sdid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.descriptors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.descriptors.is_empty()
+    }
+}
+
+#[cfg(test)]
+fn binary_sid(authority: u8, subs: &[u32]) -> Vec<u8> {
+    let mut b = vec![1, subs.len() as u8, 0, 0, 0, 0, 0, authority];
+    for s in subs {
+        b.extend(s.to_le_bytes());
+    }
+    b
+}
+
+#[test]
+fn test_secstore() {
+    let alice = binary_sid(5, &[21, 1, 2, 3, 1001]);
+    let admins = binary_sid(5, &[32, 544]);
+    let system = binary_sid(5, &[18]);
+    let ace = |ace_type: u8, flags: u8, mask: u32, sid: &[u8]| {
+        let mut b = vec![ace_type, flags];
+        b.extend((8 + sid.len() as u16).to_le_bytes());
+        b.extend(mask.to_le_bytes());
+        b.extend(sid);
+        b
+    };
+    let aces = [
+        ace(0, 0, 0x001F01FF, &alice),
+        ace(0, 0x10, 0x001F01FF, &system),
+        ace(1, 0, 0x00100116, &admins),
+        ace(0, 0, 0x00000001, &admins),
+    ]
+    .concat();
+    let mut acl = vec![2, 0];
+    acl.extend((8 + aces.len() as u16).to_le_bytes());
+    acl.extend(4_u16.to_le_bytes());
+    acl.extend([0, 0]);
+    acl.extend(aces);
+
+    let owner_offset = SD_HEADER_LEN;
+    let group_offset = owner_offset + alice.len();
+    let dacl_offset = group_offset + admins.len();
+    let mut sd = vec![1, 0];
+    sd.extend((SE_SELF_RELATIVE | SE_DACL_PRESENT).to_le_bytes());
+    for offset in [owner_offset, group_offset, 0, dacl_offset] {
+        sd.extend((offset as u32).to_le_bytes());
+    }
+    sd.extend(&alice);
+    sd.extend(&admins);
+    sd.extend(&acl);
+
+    let parsed = parse_security_descriptor(&sd).unwrap();
+    assert_eq!(parsed.owner.as_deref(), Some("S-1-5-21-1-2-3-1001"));
+    assert_eq!(parsed.group.as_deref(), Some("S-1-5-32-544"));
+    assert_eq!(
+        parsed.acl_summary(&SidResolver::default()),
+        [
+            "Allow S-1-5-21-1-2-3-1001: FullControl",
+            "Allow SYSTEM: FullControl (inherited)",
+            "Deny Administrators: Write",
+            "Allow Administrators: 0x00000001",
+        ]
+    );
+    assert_eq!(parse_security_descriptor(&sd[1..]), None);
+
+    // records between unrelated bytes
+    let mut data = vec![0xff; 6];
+    for sdid in [7_u32, 9] {
+        data.extend(sdid.to_le_bytes());
+        data.extend((sd.len() as u32).to_le_bytes());
+        data.extend(&sd);
+        data.extend([0; 3]);
+    }
+    let mut store = SecStore::default();
+    store.add_records(&data);
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.get(9), Some(&parsed));
+    assert_eq!(store.get(8), None);
+}
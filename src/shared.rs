@@ -1,47 +1,1088 @@
+use chrono::{DateTime, Utc};
 use simple_error::SimpleError;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
+use crate::classify::*;
+use crate::mft::*;
+use crate::mount::*;
+use crate::properties::*;
 use crate::report::*;
+use crate::secstore::*;
+use crate::sids::*;
+use crate::timeline::*;
+use crate::utils::*;
 use ese_parser_lib::parser::jet::DbState;
 use std::io::Write;
 
-type Reports = (
-    Box<dyn Report>, /* file report */
-    Box<dyn Report>, /* ie report */
-    Box<dyn Report>, /* act report */
-);
+/// The SecStore next to a database, empty when it was not collected along with it.
+pub fn load_secstore(db: &Path, status_logger: &mut Box<dyn Write>) -> SecStore {
+    let Some(dir) = SecStore::find(db) else {
+        return SecStore::default();
+    };
+    match SecStore::load(&dir) {
+        Ok(secstore) if secstore.is_empty() => {
+            eprintln!(
+                "WARNING: No security descriptors found in {}, its format may differ from the \
+                 one sidr reads. The Owner, Group and ACL columns are left empty.",
+                dir.to_string_lossy()
+            );
+            secstore
+        }
+        Ok(secstore) => {
+            let _ = writeln!(
+                status_logger,
+                "Found {} security descriptors in {}",
+                secstore.len(),
+                dir.to_string_lossy()
+            );
+            secstore
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            SecStore::default()
+        }
+    }
+}
+
+pub fn is_valid_file(p: &Path) -> bool {
+    let is_valid_name = p
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map_or(false, |name| {
+            let name = name.to_ascii_lowercase();
+            name == "windows" || name.starts_with("s-1-")
+        });
+    let is_valid_ext = p.extension().and_then(|e| e.to_str()).map_or(false, |ext| {
+        let ext = ext.to_ascii_lowercase();
+        ext == "edb" || ext == "db"
+    });
+    is_valid_name && is_valid_ext
+}
+
+/// Splits System_Activity_ContentUri into VolumeId, ObjectId, KnownFolderId and KnownFolderLength.
+/// A time-based ObjectId also gives the file's birth time, clock sequence and the MAC address
+/// of the machine it was created on.
+pub fn insert_content_uri_fields(r: &dyn Report, uri: &str) {
+    r.insert_str_val("VolumeId", find_guid(uri, "VolumeId="));
+    let object_id = find_guid(uri, "ObjectId=");
+    if let Some(uuid) = decode_uuid_v1(&object_id) {
+        r.insert_str_val("ObjectIdTimestamp", format_date_time(uuid.timestamp));
+        r.insert_int_val("ObjectIdClockSequence", uuid.clock_sequence as u64);
+        r.insert_str_val("ObjectIdMacAddress", uuid.node);
+    }
+    r.insert_str_val("ObjectId", object_id);
+    if let Some(id) = find_uri_param(uri, "KnownFolderId") {
+        r.insert_str_val("KnownFolderId", id.to_string());
+    }
+    if let Some(len) = find_uri_param(uri, "KnownFolderLength").and_then(|l| l.parse().ok()) {
+        r.insert_int_val("KnownFolderLength", len);
+    }
+}
+
+/// Application and ApplicationPlatform of an activity, the primary entry of its
+/// System_ActivityHistory_AppId with known folders resolved.
+pub fn insert_activity_app_fields(r: &dyn Report, app_id: &str) {
+    let apps = parse_activity_app_id(app_id);
+    if let Some(app) = primary_activity_app(&apps) {
+        r.insert_str_val("Application", resolve_known_folder(&app.application));
+        r.insert_str_val("ApplicationPlatform", app.platform.clone());
+    }
+}
+
+/// Columns of the File report that are not plain strings or dates, the SID and name of the
+/// file owner, and the ones derived from the path: parent folder, file name, extension and
+/// the user profile it is in.
+pub fn insert_file_fields(r: &dyn Report, rec: &dyn PropertyRecord, sids: &SidResolver) {
+    // all columns are set, so that the CSV header has them even if the first record misses some
+    for col in ["FileAttributeNames", "ParentFolder", "FileName", "Extension", "UserProfile"] {
+        r.set_field(col);
+    }
+    sids.set_sid_fields(r, "FileOwner");
+    if let Some(attributes) = rec.get_int("System.FileAttributes") {
+        r.insert_int_val("System_FileAttributes", attributes as u64);
+        r.insert_str_val("FileAttributeNames", file_attributes_flags_to_string(attributes as u32));
+    }
+    if let Some(kind) = rec.get_str_list("System.Kind") {
+        r.insert_str_list_val("System_Kind", kind);
+    }
+    // the account name (DESKTOP-1\alice), or its SID when the indexer could not resolve it
+    if let Some(owner) = rec.get_str("System.FileOwner") {
+        if owner.to_uppercase().starts_with("S-1-") {
+            sids.insert_sid(r, "FileOwner", &owner);
+        } else if let Some(sid) = sids.find_sid(&owner) {
+            sids.insert_sid(r, "FileOwner", sid);
+        }
+    }
+    let is_folder = rec.get_bool("System.IsFolder");
+    if let Some(is_folder) = is_folder {
+        r.insert_str_val("System_IsFolder", is_folder.to_string());
+    }
+    if let Some(path) = rec.get_str("System.ItemPathDisplay") {
+        let (parent, name) = split_item_path(&path);
+        r.insert_str_val("ParentFolder", parent.to_string());
+        r.insert_str_val("FileName", name.to_string());
+        if is_folder != Some(true) {
+            if let Some(ext) = file_extension(name) {
+                r.insert_str_val("Extension", ext.to_string());
+            }
+        }
+        if let Some(profile) = user_profile_from_path(&path) {
+            r.insert_str_val("UserProfile", profile.to_string());
+        }
+    }
+}
+
+/// FileStatus of an indexed file on the mounted volumes (Exists, Deleted or Changed),
+/// with its current size and modification time.
+pub fn insert_mount_fields(r: &dyn Report, rec: &dyn PropertyRecord, mounts: &MountedVolumes) {
+    if mounts.is_empty() {
+        return;
+    }
+    for col in ["FileStatus", "CurrentSize", "CurrentDateModified"] {
+        r.set_field(col);
+    }
+    let Some(path) = rec.get_str("System.ItemPathDisplay") else {
+        return;
+    };
+    let is_folder = rec.get_bool("System.IsFolder") == Some(true);
+    let size = rec.get_int("System.Size");
+    let modified = rec.get_datetime("System.DateModified");
+    if let Some(current) = mounts.check(&path, size, modified, is_folder) {
+        r.insert_str_val("FileStatus", current.status.as_str().to_string());
+        if let Some(size) = current.size {
+            r.insert_int_val("CurrentSize", size);
+        }
+        if let Some(modified) = current.modified {
+            r.insert_str_val("CurrentDateModified", format_date_time(modified));
+        }
+    }
+}
 
-pub fn init_reports(
-    f: &Path,
-    report_prod: &ReportProducer,
-    recovered_hostname: &str,
+/// SDID of an item, with the owner, group and DACL of its security descriptor in the SecStore.
+/// Only the columns are set for items without an SDID.
+pub fn insert_sdid_fields(
+    r: &dyn Report,
+    sdid: Option<u32>,
+    secstore: &SecStore,
+    sids: &SidResolver,
+) {
+    r.set_field("SDID");
+    sids.set_sid_fields(r, "Owner");
+    sids.set_sid_fields(r, "Group");
+    r.set_field("ACL");
+    let Some(sdid) = sdid else {
+        return;
+    };
+    r.insert_int_val("SDID", sdid as u64);
+    let Some(sd) = secstore.get(sdid) else {
+        return;
+    };
+    if let Some(owner) = &sd.owner {
+        sids.insert_sid(r, "Owner", owner);
+    }
+    if let Some(group) = &sd.group {
+        sids.insert_sid(r, "Group", group);
+    }
+    r.insert_str_list_val("ACL", sd.acl_summary(sids));
+}
+
+/// Columns of the Activity History report computed from the properties: duration, user,
+/// application and the parts of System_Activity_ContentUri.
+pub fn insert_activity_fields(r: &dyn Report, rec: &dyn PropertyRecord, sids: &SidResolver) {
+    let start = rec.get_datetime("System.ActivityHistory.StartTime");
+    let end = rec.get_datetime("System.ActivityHistory.EndTime");
+    if let (Some(start), Some(end)) = (start, end) {
+        if end >= start {
+            r.insert_int_val("DurationSeconds", (end - start).num_seconds() as u64);
+        }
+    }
+    if let Some(url) = rec.get_str("System.ItemUrl") {
+        if let Some(sid) = sid_from_item_url(&url) {
+            sids.insert_sid(r, "User", sid);
+        }
+    }
+    if let Some(app_id) = rec.get_str("System.ActivityHistory.AppId") {
+        insert_activity_app_fields(r, &app_id);
+    }
+    if let Some(uri) = rec.get_str("System.Activity.ContentUri") {
+        insert_content_uri_fields(r, &uri);
+    }
+}
+
+/// Match with the Windows Timeline (ActivitiesCache.db) of an Activity History record and the
+/// fields only the Timeline has. Records without a match get a TimelineMatch of NotInTimeline.
+pub fn insert_timeline_fields(r: &dyn Report, rec: &dyn PropertyRecord, timeline: &Timeline) {
+    if timeline.is_empty() {
+        return;
+    }
+    let id = rec.get_guid("System.ActivityHistory.Id");
+    let app_id = rec.get_str("System.ActivityHistory.AppId");
+    let app_activity_id = rec.get_str("System.ActivityHistory.AppActivityId");
+    let content_uri = rec.get_str("System.Activity.ContentUri");
+    let activity = IndexedActivity {
+        id: id.as_deref(),
+        app_id: app_id.as_deref(),
+        app_activity_id: app_activity_id.as_deref(),
+        content_uri: content_uri.as_deref(),
+        start_time: rec.get_datetime("System.ActivityHistory.StartTime"),
+    };
+    let Some((m, t)) = timeline.find(&activity) else {
+        r.insert_str_val("TimelineMatch", "NotInTimeline".to_string());
+        return;
+    };
+    r.insert_str_val("TimelineMatch", m.as_str().to_string());
+    if let Some(activity_type) = t.activity_type {
+        r.insert_str_val("TimelineActivityType", activity_type_to_string(activity_type));
+    }
+    if let Some(status) = t.activity_status {
+        r.insert_int_val("TimelineActivityStatus", status as u64);
+    }
+    for (col, val) in [
+        ("TimelineDisplayText", &t.payload.display_text),
+        ("TimelineDescription", &t.payload.description),
+        ("TimelineAppDisplayName", &t.payload.app_display_name),
+        ("TimelinePlatformDeviceId", &t.platform_device_id),
+    ] {
+        if let Some(val) = val {
+            r.insert_str_val(col, val.clone());
+        }
+    }
+    if let Some(duration) = t.payload.active_duration_seconds {
+        r.insert_int_val("TimelineActiveDurationSeconds", duration as u64);
+    }
+    for (col, time) in [
+        ("TimelineStartTime", t.start_time),
+        ("TimelineEndTime", t.end_time),
+        ("TimelineLastModifiedTime", t.last_modified_time),
+        ("TimelineExpirationTime", t.expiration_time),
+    ] {
+        if let Some(time) = time {
+            r.insert_str_val(col, format_date_time(time));
+        }
+    }
+    if let Some(local) = t.is_local_only {
+        r.insert_str_val("TimelineIsLocalOnly", local.to_string());
+    }
+    r.insert_str_val("TimelineDatabase", t.database.clone());
+}
+
+/// Browser, Channel and Profile of an internet history record, from its System_ItemUrl.
+pub fn insert_browser_fields(r: &dyn Report, item_url: &str) {
+    for col in ["Browser", "Channel", "Profile"] {
+        r.set_field(col);
+    }
+    if let Some(src) = parse_browser_item_url(item_url) {
+        r.insert_str_val("Browser", src.browser.to_string());
+        r.insert_str_val("Channel", src.channel.to_string());
+        r.insert_str_val("Profile", src.profile.to_string());
+    }
+}
+
+/// Writes a row of a report whose columns are the same for Windows.edb and Windows.db.
+/// All columns are set, so that the CSV header has them even if the first record misses some.
+pub fn write_property_row(r: &mut dyn Report, workId: u32, rec: &dyn PropertyRecord, layout: &ReportLayout) {
+    r.create_new_row(false);
+    for (col, _) in layout.columns() {
+        r.set_field(&col);
+    }
+    r.insert_int_val("WorkId", workId as u64);
+    write_properties(r, rec, layout.properties);
+}
+
+/// Writes a file record to the reports kept in addition to the File report
+/// (Document Metadata, Media, Cloud Files and Shortcuts), and collects applications.
+pub fn write_file_extras(
+    report_set: &mut ReportSet,
+    hostname: Option<&str>,
+    workId: u32,
+    rec: &dyn PropertyRecord,
+    sids: &SidResolver,
     status_logger: &mut Box<dyn Write>,
+) -> Result<(), SimpleError> {
+    if is_document(rec) {
+        let r = report_set.get_additional(hostname, DOCUMENT_METADATA, status_logger)?;
+        write_property_row(&mut **r, workId, rec, &DOCUMENT_REPORT);
+    }
+    if is_media(rec) {
+        let r = report_set.get_additional(hostname, MEDIA, status_logger)?;
+        write_property_row(&mut **r, workId, rec, &MEDIA_REPORT);
+        if let Some(duration) = rec.get_int("System.Media.Duration") {
+            r.insert_str_val("DurationSeconds", (duration as f64 / 10_000_000.0).to_string());
+        }
+        let latitude = gps_coordinate(rec, "Latitude");
+        let longitude = gps_coordinate(rec, "Longitude");
+        if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+            r.insert_str_val("Latitude", latitude.to_string());
+            r.insert_str_val("Longitude", longitude.to_string());
+            report_set.add_location(hostname, workId, rec, latitude, longitude);
+        }
+    }
+    if is_cloud_file(rec) {
+        let r = report_set.get_additional(hostname, CLOUD_FILES, status_logger)?;
+        write_property_row(&mut **r, workId, rec, &CLOUD_REPORT);
+        insert_cloud_fields(&**r, rec, sids);
+    }
+    if is_shortcut(rec) {
+        let r = report_set.get_additional(hostname, SHORTCUTS, status_logger)?;
+        write_property_row(&mut **r, workId, rec, &SHORTCUT_REPORT);
+        insert_shortcut_fields(&**r, rec);
+    }
+    if is_application(rec) {
+        report_set.add_application(hostname, workId, rec);
+    }
+    Ok(())
+}
+
+// Index and $MFT timestamps closer than this are the same.
+const MFT_TIME_TOLERANCE_SECONDS: i64 = 1;
+
+fn times_differ(indexed: Option<DateTime<Utc>>, mft: Option<DateTime<Utc>>) -> bool {
+    match (indexed, mft) {
+        (Some(indexed), Some(mft)) => (indexed - mft).num_seconds().abs() > MFT_TIME_TOLERANCE_SECONDS,
+        _ => false,
+    }
+}
+
+/// Differences between an indexed file, or the file of an activity, and the $MFT,
+/// with the MFT entry it was compared with.
+///
+/// Files are looked up by System_ItemPathDisplay, then by System_FileFRN to find the ones that
+/// were moved or renamed; activities by the ObjectId of System_Activity_ContentUri.
+/// Paths on other volumes are not compared.
+pub fn mft_findings<'m>(rec: &dyn PropertyRecord, mft: &'m Mft) -> (Vec<&'static str>, Option<&'m MftEntry>) {
+    let mut findings = Vec::new();
+    let path = rec.get_str("System.ItemPathDisplay").filter(|p| mft.covers(p));
+    let entry = if let Some(path) = &path {
+        let entry = mft.find_path(path);
+        let moved = match entry {
+            Some(_) => None,
+            None => rec
+                .get_int("System.FileFRN")
+                .and_then(|frn| mft.find_frn(frn as u64))
+                .filter(|e| e.in_use),
+        };
+        match (entry, moved) {
+            (None, Some(_)) => findings.push("MovedInMft"),
+            (None, None) => findings.push("NotInMft"),
+            _ => {}
+        }
+        entry.or(moved)
+    } else if let Some(uri) = rec.get_str("System.Activity.ContentUri") {
+        let object_id = find_guid(&uri, "ObjectId=");
+        if object_id.is_empty() {
+            return (findings, None);
+        }
+        let entry = mft.find_object_id(&object_id);
+        if entry.is_none() {
+            findings.push("ObjectIdNotInMft");
+        }
+        entry
+    } else {
+        None
+    };
+    if let Some(e) = entry {
+        if !e.in_use {
+            findings.push("DeletedInMft");
+        } else if path.is_some() {
+            if times_differ(rec.get_datetime("System.DateCreated"), e.si.created) {
+                findings.push("CreatedDiffers");
+            }
+            if times_differ(rec.get_datetime("System.DateModified"), e.si.modified) {
+                findings.push("ModifiedDiffers");
+            }
+            if times_differ(rec.get_datetime("System.DateCreated"), e.fn_times.created) {
+                findings.push("FnCreatedDiffers");
+            }
+            if times_differ(rec.get_datetime("System.DateModified"), e.fn_times.modified) {
+                findings.push("FnModifiedDiffers");
+            }
+        }
+        // $STANDARD_INFORMATION can be set from user mode, $FILE_NAME can't:
+        // a file created before its name was is likely timestomped
+        if let (Some(si), Some(fname)) = (e.si.created, e.fn_times.created) {
+            if (fname - si).num_seconds() > MFT_TIME_TOLERANCE_SECONDS {
+                findings.push("SiCreatedBeforeFn");
+            }
+        }
+    }
+    (findings, entry)
+}
+
+/// Writes a row to the MFT Comparison report when a record disagrees with the $MFT.
+pub fn write_mft_comparison(
+    report_set: &mut ReportSet,
+    hostname: Option<&str>,
+    workId: u32,
+    rec: &dyn PropertyRecord,
+    mft: &Mft,
+    status_logger: &mut Box<dyn Write>,
+) -> Result<(), SimpleError> {
+    let (findings, entry) = mft_findings(rec, mft);
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let r = report_set.get_additional(hostname, MFT_COMPARISON, status_logger)?;
+    write_property_row(&mut **r, workId, rec, &MFT_REPORT);
+    r.insert_str_list_val("Finding", findings.iter().map(|f| f.to_string()).collect());
+    if let Some(uri) = rec.get_str("System.Activity.ContentUri") {
+        r.insert_str_val("ObjectId", find_guid(&uri, "ObjectId="));
+    }
+    if let Some(e) = entry {
+        r.insert_int_val("MftEntry", e.entry);
+        r.insert_int_val("MftSequence", e.sequence as u64);
+        r.insert_str_val("MftInUse", e.in_use.to_string());
+        r.insert_str_val("MftPath", format!("{}:{}", mft.drive(), e.path));
+        for (col, time) in [
+            ("SiCreated", e.si.created),
+            ("SiModified", e.si.modified),
+            ("FnCreated", e.fn_times.created),
+            ("FnModified", e.fn_times.modified),
+        ] {
+            if let Some(time) = time {
+                r.insert_str_val(col, format_date_time(time));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Counts the use of the application of an Activity History record.
+pub fn record_app_usage(report_set: &mut ReportSet, hostname: Option<&str>, rec: &dyn PropertyRecord) {
+    let Some(app_id) = rec.get_str("System.ActivityHistory.AppId") else {
+        return;
+    };
+    let start = rec.get_datetime("System.ActivityHistory.StartTime");
+    let end = rec.get_datetime("System.ActivityHistory.EndTime").or(start);
+    for app in activity_app_ids(&app_id) {
+        report_set.add_app_usage(hostname, &app, start, end);
+    }
+}
+
+/// Decoded storage provider, sync and pin state of a cloud file, and whether it is in a
+/// SharePoint library.
+pub fn insert_cloud_fields(r: &dyn Report, rec: &dyn PropertyRecord, sids: &SidResolver) {
+    if let Some(id) = rec.get_str("System.StorageProviderId") {
+        if let Some(p) = parse_storage_provider_id(&id) {
+            r.insert_str_val("CloudProvider", p.provider.to_string());
+            if let Some(account) = p.account {
+                r.insert_str_val("CloudAccount", account.to_string());
+            }
+            if let Some(sid) = p.sid {
+                sids.insert_sid(r, "CloudUser", sid);
+            }
+        }
+    }
+    if let Some(uri) = rec.get_str("System.StorageProviderFileRemoteUri") {
+        let sharepoint = uri.to_ascii_lowercase().contains(".sharepoint.com/");
+        r.insert_str_val("SharePoint", sharepoint.to_string());
+    }
+    if let Some(state) = rec.get_int("System.StorageProviderState") {
+        r.insert_str_val("ProviderState", storage_provider_state_to_string(state));
+    }
+    if let Some(status) = rec.get_int("System.SyncTransferStatus") {
+        r.insert_str_val("SyncStatus", sync_transfer_status_to_string(status as u32));
+    }
+    if let Some(status) = rec.get_int("System.FilePlaceholderStatus") {
+        r.insert_str_val("PlaceholderStatus", placeholder_status_to_string(status as u32));
+    }
+    if let Some(attributes) = rec.get_int("System.FileAttributes") {
+        r.insert_str_val("PinState", pin_state(attributes as u32).to_string());
+    }
+}
+
+/// Decoded target attributes of a shortcut.
+pub fn insert_shortcut_fields(r: &dyn Report, rec: &dyn PropertyRecord) {
+    if let Some(flags) = rec.get_int("System.Link.TargetSFGAOFlags") {
+        r.insert_str_val("TargetAttributes", sfgao_flags_to_string(flags as u32));
+    }
+}
+
+fn insert_app_usage(r: &dyn Report, usage: &AppUsage) {
+    r.insert_int_val("UsageCount", usage.count);
+    if let Some(first) = usage.first {
+        r.insert_str_val("FirstUsed", format_date_time(first));
+    }
+    if let Some(last) = usage.last {
+        r.insert_str_val("LastUsed", format_date_time(last));
+    }
+}
+
+/// The user a per-user database (S-1-5-21-...-1001.db) belongs to, from its file name.
+pub struct DbOwner {
+    pub sid: String,
+    /// Set when SIDs were loaded, empty when the SID is not resolved.
+    pub name: Option<String>,
+}
+
+impl DbOwner {
+    pub fn of_database(f: &Path, sids: &SidResolver) -> Option<Self> {
+        let sid = f
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|s| s.to_uppercase().starts_with("S-1-"))?;
+        let name = (!sids.is_empty()).then(|| {
+            sids.resolve(sid)
+                .and_then(|u| u.name.clone())
+                .unwrap_or_default()
+        });
+        Some(DbOwner {
+            sid: sid.to_string(),
+            name,
+        })
+    }
+}
+
+// Every row of the reports of a per-user database records the user it belongs to.
+fn with_db_owner(rep: Box<dyn Report>, db_owner: Option<&DbOwner>) -> Box<dyn Report> {
+    match db_owner {
+        Some(owner) => Box::new(ReportWithDbOwner::new(rep, &owner.sid, owner.name.clone())),
+        None => rep,
+    }
+}
+
+// When the database was extracted from a collection archive,
+// every row records where it came from.
+fn with_archive_src(rep: Box<dyn Report>, archive_src: Option<&str>) -> Box<dyn Report> {
+    match archive_src {
+        Some(src) => Box::new(ReportWithSource::new(rep, src)),
+        None => rep,
+    }
+}
+
+/// Settings shared by every database being processed.
+#[derive(Default)]
+pub struct DumpOptions {
+    pub classifier: Classifier,
+    pub sids: SidResolver,
+    /// $MFT the indexed files are compared with.
+    pub mft: Option<Mft>,
+    /// Mounted volumes the indexed files are checked against.
+    pub mounts: MountedVolumes,
+    /// Windows Timeline activities the Activity History records are joined with.
+    pub timeline: Timeline,
+}
+
+/// The reports of one database.
+///
+/// Every record keeps its own System_ComputerName. The reports are named after the hostname
+/// recovered for the whole database, unless the producer splits the output per hostname;
+/// then the reports of each other hostname are created when its first record shows up.
+/// The default categories get a report as soon as a hostname does, other categories
+/// when their first record shows up.
+pub struct ReportSet<'a> {
+    f: &'a Path,
+    archive_src: Option<&'a str>,
+    db_owner: Option<DbOwner>,
+    report_prod: &'a ReportProducer,
     edb_database_state: Option<DbState>,
-) -> Result<Reports, SimpleError> {
-    let (file_rep_path, file_rep) =
-        report_prod.new_report(f, recovered_hostname, "File_Report", edb_database_state)?;
-
-    let (ie_rep_path, ie_rep) = report_prod.new_report(
-        f,
-        recovered_hostname,
-        "Internet_History_Report",
-        edb_database_state,
-    )?;
-
-    let (act_rep_path, act_rep) = report_prod.new_report(
-        f,
-        recovered_hostname,
-        "Activity_History_Report",
-        edb_database_state,
-    )?;
-
-    writeln!(
-        status_logger,
-        "{}\n{}\n{}\n",
-        file_rep_path.to_string_lossy(),
-        ie_rep_path.to_string_lossy(),
-        act_rep_path.to_string_lossy()
-    )
-    .map_err(|e| SimpleError::new(format!("{e}")))?;
-    Ok((file_rep, ie_rep, act_rep))
+    recovered_hostname: String,
+    reports: HashMap<(String /*hostname*/, String /*category*/), Box<dyn Report>>,
+    hostnames: BTreeMap<String /*hostname*/, u64 /*records*/>,
+    // GeoJSON features of geotagged media, if the producer writes them
+    locations: BTreeMap<String /*hostname*/, Vec<serde_json::Value>>,
+    // indexed applications and their use in Activity History, written when the database is done
+    applications: Vec<(String /*hostname*/, Vec<(String, PropValue)>)>,
+    app_usage: BTreeMap<(String /*hostname*/, String /*app id, lowercase*/), AppUsage>,
+}
+
+// Use of an application in Activity History.
+struct AppUsage {
+    app_id: String,
+    count: u64,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+}
+
+impl<'a> ReportSet<'a> {
+    pub fn new(
+        f: &'a Path,
+        archive_src: Option<&'a str>,
+        db_owner: Option<DbOwner>,
+        report_prod: &'a ReportProducer,
+        recovered_hostname: &str,
+        status_logger: &mut Box<dyn Write>,
+        edb_database_state: Option<DbState>,
+    ) -> Result<Self, SimpleError> {
+        let mut report_set = ReportSet {
+            f,
+            archive_src,
+            db_owner,
+            report_prod,
+            edb_database_state,
+            recovered_hostname: recovered_hostname.to_string(),
+            reports: HashMap::new(),
+            hostnames: BTreeMap::new(),
+            locations: BTreeMap::new(),
+            applications: Vec::new(),
+            app_usage: BTreeMap::new(),
+        };
+        report_set.init_reports(recovered_hostname, status_logger)?;
+        Ok(report_set)
+    }
+
+    fn new_report(
+        &mut self,
+        hostname: &str,
+        category: &str,
+    ) -> Result<PathBuf, SimpleError> {
+        let (path, rep) = self.report_prod.new_report(
+            self.f,
+            hostname,
+            &format!("{category}_Report"),
+            self.edb_database_state,
+        )?;
+        self.reports.insert(
+            (hostname.to_string(), category.to_string()),
+            with_db_owner(
+                with_archive_src(rep, self.archive_src),
+                self.db_owner.as_ref(),
+            ),
+        );
+        Ok(path)
+    }
+
+    fn init_reports(
+        &mut self,
+        hostname: &str,
+        status_logger: &mut Box<dyn Write>,
+    ) -> Result<(), SimpleError> {
+        for category in DEFAULT_CATEGORIES {
+            let path = self.new_report(hostname, category)?;
+            writeln!(status_logger, "{}", path.to_string_lossy())
+                .map_err(|e| SimpleError::new(format!("{e}")))?;
+        }
+        writeln!(status_logger).map_err(|e| SimpleError::new(format!("{e}")))
+    }
+
+    /// Report of the given category for a record with the given System_ComputerName.
+    pub fn get(
+        &mut self,
+        hostname: Option<&str>,
+        category: &str,
+        status_logger: &mut Box<dyn Write>,
+    ) -> Result<&mut Box<dyn Report>, SimpleError> {
+        if let Some(h) = hostname.filter(|h| !h.is_empty()) {
+            *self.hostnames.entry(h.to_string()).or_insert(0) += 1;
+        }
+        self.get_additional(hostname, category, status_logger)
+    }
+
+    /// Like `get`, for a record already counted for the hostname summary
+    /// (written to more than one report).
+    pub fn get_additional(
+        &mut self,
+        hostname: Option<&str>,
+        category: &str,
+        status_logger: &mut Box<dyn Write>,
+    ) -> Result<&mut Box<dyn Report>, SimpleError> {
+        let hostname = self.report_hostname(hostname);
+        let key = (hostname.clone(), category.to_string());
+        if !self.reports.contains_key(&key) {
+            if !self.reports.keys().any(|(h, _)| *h == hostname) {
+                self.init_reports(&hostname, status_logger)?;
+            }
+            if !self.reports.contains_key(&key) {
+                let path = self.new_report(&hostname, category)?;
+                writeln!(status_logger, "{}", path.to_string_lossy())
+                    .map_err(|e| SimpleError::new(format!("{e}")))?;
+            }
+        }
+        Ok(self.reports.get_mut(&key).unwrap())
+    }
+
+    // Hostname the reports of a record are named after.
+    fn report_hostname(&self, hostname: Option<&str>) -> String {
+        match hostname {
+            Some(h) if !h.is_empty() && self.report_prod.split_by_hostname() => h.to_string(),
+            _ => self.recovered_hostname.clone(),
+        }
+    }
+
+    pub fn add_application(&mut self, hostname: Option<&str>, workId: u32, rec: &dyn PropertyRecord) {
+        let mut values = vec![("WorkId".to_string(), PropValue::Int(workId as u64))];
+        values.extend(read_properties(rec, APPLICATION_REPORT.properties));
+        let hostname = self.report_hostname(hostname);
+        self.applications.push((hostname, values));
+    }
+
+    pub fn add_app_usage(
+        &mut self,
+        hostname: Option<&str>,
+        app_id: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) {
+        let key = (self.report_hostname(hostname), app_id.to_lowercase());
+        let usage = self.app_usage.entry(key).or_insert_with(|| AppUsage {
+            app_id: app_id.to_string(),
+            count: 0,
+            first: None,
+            last: None,
+        });
+        usage.count += 1;
+        usage.first = usage.first.into_iter().chain(start).min();
+        usage.last = usage.last.into_iter().chain(end).max();
+    }
+
+    /// Writes the Applications report: the indexed applications with their use in
+    /// Activity History, then the applications only found in Activity History.
+    fn write_applications(&mut self, status_logger: &mut Box<dyn Write>) -> Result<(), SimpleError> {
+        let applications = std::mem::take(&mut self.applications);
+        let mut app_usage = std::mem::take(&mut self.app_usage);
+        for (hostname, values) in applications {
+            let usage = values
+                .iter()
+                .find(|(col, _)| col == "System_AppUserModel_ID")
+                .and_then(|(_, v)| match v {
+                    PropValue::Str(id) => app_usage.remove(&(hostname.clone(), id.to_lowercase())),
+                    PropValue::StrList(_) | PropValue::Int(_) => None,
+                });
+            let r = self.get_additional(Some(&hostname), APPLICATIONS, status_logger)?;
+            r.create_new_row(false);
+            for (col, _) in APPLICATION_REPORT.columns() {
+                r.set_field(&col);
+            }
+            write_values(&**r, &values);
+            r.insert_str_val("Indexed", "true".to_string());
+            if let Some(usage) = usage {
+                insert_app_usage(&**r, &usage);
+            }
+        }
+        for ((hostname, _), usage) in app_usage {
+            let r = self.get_additional(Some(&hostname), APPLICATIONS, status_logger)?;
+            r.create_new_row(false);
+            for (col, _) in APPLICATION_REPORT.columns() {
+                r.set_field(&col);
+            }
+            r.insert_str_val("System_AppUserModel_ID", usage.app_id.clone());
+            r.insert_str_val("Indexed", "false".to_string());
+            insert_app_usage(&**r, &usage);
+        }
+        Ok(())
+    }
+
+    /// Writes what is collected over the whole database, once all records are processed.
+    pub fn finish(&mut self, status_logger: &mut Box<dyn Write>) -> Result<(), SimpleError> {
+        self.write_applications(status_logger)?;
+        self.write_locations(status_logger)?;
+        self.write_hostname_summary(status_logger)
+    }
+
+    /// Records the location of a geotagged media file for `write_locations`.
+    pub fn add_location(
+        &mut self,
+        hostname: Option<&str>,
+        workId: u32,
+        rec: &dyn PropertyRecord,
+        latitude: f64,
+        longitude: f64,
+    ) {
+        if !self.report_prod.geojson() {
+            return;
+        }
+        let mut properties = serde_json::Map::new();
+        properties.insert("WorkId".to_string(), workId.into());
+        for name in [
+            "System.ComputerName",
+            "System.ItemPathDisplay",
+            "System.Photo.CameraModel",
+        ] {
+            if let Some(v) = rec.get_str(name) {
+                properties.insert(column_name(name), v.into());
+            }
+        }
+        if let Some(v) = rec.get_datetime("System.Photo.DateTaken") {
+            properties.insert("System_Photo_DateTaken".to_string(), format_date_time(v).into());
+        }
+        let feature = serde_json::json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": [longitude, latitude] },
+            "properties": properties,
+        });
+        let hostname = self.report_hostname(hostname);
+        self.locations.entry(hostname).or_default().push(feature);
+    }
+
+    /// Writes the locations of geotagged media of every hostname as a GeoJSON feature collection.
+    fn write_locations(&self, status_logger: &mut Box<dyn Write>) -> Result<(), SimpleError> {
+        for (hostname, features) in &self.locations {
+            let path = self.report_prod.get_path_db_status(
+                hostname,
+                "Media_Locations",
+                Utc::now(),
+                "geojson",
+                self.edb_database_state,
+            );
+            let collection = serde_json::json!({
+                "type": "FeatureCollection",
+                "features": features,
+            });
+            std::fs::write(&path, collection.to_string())
+                .map_err(|e| SimpleError::new(format!("{}: {e}", path.to_string_lossy())))?;
+            writeln!(status_logger, "{}", path.to_string_lossy())
+                .map_err(|e| SimpleError::new(format!("{e}")))?;
+        }
+        Ok(())
+    }
+
+    /// Lists every System_ComputerName found in the database, warns if there is more than one.
+    fn write_hostname_summary(&self, status_logger: &mut Box<dyn Write>) -> Result<(), SimpleError> {
+        if self.hostnames.len() > 1 {
+            eprintln!(
+                "WARNING: {} different hostnames found in System_ComputerName, reports are named after '{}'.{}",
+                self.hostnames.len(),
+                self.recovered_hostname,
+                if self.report_prod.split_by_hostname() {
+                    ""
+                } else {
+                    " Use --split-by-hostname to write separate reports per hostname."
+                }
+            );
+        }
+        if !self.hostnames.is_empty() {
+            writeln!(status_logger, "Hostnames found:")
+                .map_err(|e| SimpleError::new(format!("{e}")))?;
+            for (hostname, records) in &self.hostnames {
+                writeln!(status_logger, "  {hostname}: {records} record(s)")
+                    .map_err(|e| SimpleError::new(format!("{e}")))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Producer writing to a new temporary directory, and a status logger discarding its output.
+#[cfg(test)]
+fn test_report_producer(
+    format: ReportFormat,
+) -> (tempdir::TempDir, ReportProducer, Box<dyn Write>) {
+    let dir = tempdir::TempDir::new("sidr_test").unwrap();
+    let report_prod = ReportProducer::new(dir.path(), format, ReportOutput::ToFile, None, None);
+    (dir, report_prod, Box::new(std::io::sink()))
+}
+
+// Reports of a Windows.db of HOST1.
+#[cfg(test)]
+fn test_report_set<'a>(
+    report_prod: &'a ReportProducer,
+    status_logger: &mut Box<dyn Write>,
+) -> ReportSet<'a> {
+    let f = Path::new("Windows.db");
+    ReportSet::new(f, None, None, report_prod, "HOST1", status_logger, None).unwrap()
+}
+
+#[test]
+fn test_report_set_split_by_hostname() {
+    let (dir, mut report_prod, mut status_logger) = test_report_producer(ReportFormat::Csv);
+    report_prod.set_split_by_hostname(true);
+    {
+        let mut report_set = test_report_set(&report_prod, &mut status_logger);
+        for hostname in [Some("HOST1"), Some("HOST2"), None, Some("HOST2")] {
+            let file_rep = report_set
+                .get(hostname, FILE_REPORT, &mut status_logger)
+                .unwrap();
+            file_rep.create_new_row(false);
+            file_rep.insert_str_val("System_ComputerName", hostname.unwrap_or("").to_string());
+        }
+        assert_eq!(
+            report_set.hostnames,
+            BTreeMap::from([("HOST1".to_string(), 1), ("HOST2".to_string(), 2)])
+        );
+    }
+    let files: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(files.len(), 6);
+    assert_eq!(files.iter().filter(|f| f.starts_with("HOST2_File_Report_")).count(), 1);
+}
+
+#[test]
+fn test_report_set_categories() {
+    let (dir, report_prod, mut status_logger) = test_report_producer(ReportFormat::Json);
+    {
+        let mut report_set = test_report_set(&report_prod, &mut status_logger);
+        for category in [ACTIVITY_HISTORY, "Email", "Email"] {
+            let rep = report_set
+                .get(Some("HOST1"), category, &mut status_logger)
+                .unwrap();
+            rep.create_new_row(false);
+            rep.insert_int_val("WorkId", 1);
+        }
+    }
+    let mut files: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .map(|f| f.split("_Report_").next().unwrap().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            "HOST1_Activity_History",
+            "HOST1_Email",
+            "HOST1_File",
+            "HOST1_Internet_History"
+        ]
+    );
+}
+
+#[test]
+fn test_db_owner() {
+    let sids = SidResolver::default();
+    let owner = DbOwner::of_database(Path::new("Search/S-1-5-21-1-2-3-1001.db"), &sids).unwrap();
+    assert_eq!(owner.sid, "S-1-5-21-1-2-3-1001");
+    assert_eq!(owner.name, None);
+    assert!(DbOwner::of_database(Path::new("Search/Windows.db"), &sids).is_none());
+}
+
+#[cfg(test)]
+struct TestRecord(HashMap<&'static str, &'static str>);
+
+#[cfg(test)]
+impl PropertyRecord for TestRecord {
+    fn get_str(&self, name: &str) -> Option<String> {
+        self.0.get(name).map(|v| v.to_string())
+    }
+    fn get_str_list(&self, name: &str) -> Option<Vec<String>> {
+        self.get_str(name).map(|v| vec![v])
+    }
+    fn get_datetime(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.0.get(name)?.parse().ok()
+    }
+    fn get_int(&self, name: &str) -> Option<i64> {
+        self.0.get(name)?.parse().ok()
+    }
+    fn get_bool(&self, _name: &str) -> Option<bool> {
+        None
+    }
+    fn get_float(&self, name: &str) -> Option<f64> {
+        self.0.get(name)?.parse().ok()
+    }
+    fn get_bytes(&self, name: &str) -> Option<Vec<u8>> {
+        self.0.get(name).map(|v| v.as_bytes().to_vec())
+    }
+}
+
+#[test]
+fn test_media_locations() {
+    let (dir, mut report_prod, mut status_logger) = test_report_producer(ReportFormat::Csv);
+    report_prod.set_geojson(true);
+    let mut report_set = test_report_set(&report_prod, &mut status_logger);
+    let rec = TestRecord(HashMap::from([
+        ("System.ItemPathDisplay", "C:\\Users\\u\\Pictures\\IMG_0001.jpg"),
+        ("System.GPS.LatitudeDecimal", "47.61"),
+        ("System.GPS.LongitudeDecimal", "-122.33"),
+    ]));
+    write_file_extras(&mut report_set, Some("HOST1"), 7, &rec, &SidResolver::default(), &mut status_logger)
+        .unwrap();
+    assert_eq!(report_set.hostnames.len(), 0);
+    report_set.finish(&mut status_logger).unwrap();
+    let geojson = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().map_or(false, |e| e == "geojson"))
+        .unwrap();
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(geojson).unwrap()).unwrap();
+    assert_eq!(v["type"], "FeatureCollection");
+    assert_eq!(v["features"][0]["geometry"]["coordinates"], serde_json::json!([-122.33, 47.61]));
+    assert_eq!(v["features"][0]["properties"]["WorkId"], 7);
+}
+
+#[test]
+fn test_applications() {
+    let (dir, report_prod, mut status_logger) = test_report_producer(ReportFormat::Csv);
+    {
+        let mut report_set = test_report_set(&report_prod, &mut status_logger);
+        let app = TestRecord(HashMap::from([
+            ("System.ItemNameDisplay", "Photos"),
+            ("System.AppUserModel.ID", "Microsoft.Windows.Photos_8wekyb3d8bbwe!App"),
+        ]));
+        write_file_extras(&mut report_set, None, 1, &app, &SidResolver::default(), &mut status_logger)
+            .unwrap();
+        let activity = TestRecord(HashMap::from([(
+            "System.ActivityHistory.AppId",
+            r#"[{"application":"microsoft.windows.photos_8wekyb3d8bbwe!App","platform":"windows_universal"}]"#,
+        )]));
+        record_app_usage(&mut report_set, None, &activity);
+        record_app_usage(&mut report_set, None, &activity);
+        let activity = TestRecord(HashMap::from([("System.ActivityHistory.AppId", "notepad.exe")]));
+        record_app_usage(&mut report_set, None, &activity);
+        report_set.finish(&mut status_logger).unwrap();
+    }
+    let report = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.to_string_lossy().contains("_Applications_Report_"))
+        .unwrap();
+    let mut rows = csv::Reader::from_path(report).unwrap();
+    let header = rows.headers().unwrap().clone();
+    let col = |name: &str| header.iter().position(|h| h == name).unwrap();
+    let rows: Vec<csv::StringRecord> = rows.records().map(|r| r.unwrap()).collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(&rows[0][col("System_ItemNameDisplay")], "Photos");
+    assert_eq!(&rows[0][col("UsageCount")], "2");
+    assert_eq!(&rows[1][col("System_AppUserModel_ID")], "notepad.exe");
+    assert_eq!(&rows[1][col("Indexed")], "false");
+}
+
+#[test]
+fn test_mft_findings() {
+    use chrono::TimeZone;
+    let t0 = Utc.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap();
+    let t1 = Utc.with_ymd_and_hms(2023, 6, 7, 8, 9, 10).unwrap();
+    let mut data = Vec::new();
+    for n in 0..5_u64 {
+        data.extend(mft_record(1, true, 5, &format!("$Meta{n}"), t0, t0, None));
+    }
+    data.extend(mft_record(5, true, 5, ".", t0, t0, None));
+    data.extend(mft_record(1, true, 5, "Users", t0, t0, None));
+    data.extend(mft_record(3, true, 6, "notes.txt", t0, t0, Some([0x11; 16])));
+    data.extend(mft_record(1, true, 6, "stomped.txt", t0, t1, None));
+    data.extend(mft_record(2, false, 6, "gone.txt", t1, t1, None));
+    let mft = Mft::from_reader(data.as_slice()).unwrap();
+
+    let findings = |rec: &[(&'static str, &'static str)]| {
+        mft_findings(&TestRecord(HashMap::from_iter(rec.iter().copied())), &mft).0
+    };
+    let notes = ("System.ItemPathDisplay", "C:\\Users\\notes.txt");
+    assert!(findings(&[notes, ("System.DateCreated", "2023-01-02T03:04:05Z")]).is_empty());
+    assert_eq!(
+        findings(&[notes, ("System.DateModified", "2023-01-02T03:05:00Z")]),
+        ["ModifiedDiffers", "FnModifiedDiffers"]
+    );
+    assert_eq!(findings(&[("System.ItemPathDisplay", "C:\\Users\\stomped.txt")]), ["SiCreatedBeforeFn"]);
+    // the index has the $STANDARD_INFORMATION times, $FILE_NAME has others
+    let stomped = ("System.ItemPathDisplay", "C:\\Users\\stomped.txt");
+    assert_eq!(
+        findings(&[stomped, ("System.DateCreated", "2023-01-02T03:04:05Z")]),
+        ["FnCreatedDiffers", "SiCreatedBeforeFn"]
+    );
+    assert_eq!(
+        findings(&[stomped, ("System.DateModified", "2023-06-07T08:09:10Z")]),
+        ["ModifiedDiffers", "SiCreatedBeforeFn"]
+    );
+    assert_eq!(findings(&[("System.ItemPathDisplay", "C:\\Users\\gone.txt")]), ["DeletedInMft"]);
+    assert_eq!(findings(&[("System.ItemPathDisplay", "C:\\other.txt")]), ["NotInMft"]);
+    assert_eq!(
+        findings(&[
+            ("System.ItemPathDisplay", "C:\\Users\\moved.txt"),
+            ("System.FileFRN", "844424930131975"),
+        ]),
+        ["MovedInMft"]
+    );
+    assert!(findings(&[("System.ItemPathDisplay", "D:\\other.txt")]).is_empty());
+    assert!(findings(&[(
+        "System.Activity.ContentUri",
+        "file:///C:/Users/notes.txt?VolumeId={22222222-2222-2222-2222-222222222222}&ObjectId={11111111-1111-1111-1111-111111111111}"
+    )])
+    .is_empty());
+    assert_eq!(
+        findings(&[(
+            "System.Activity.ContentUri",
+            "file:///C:/x.txt?VolumeId={22222222-2222-2222-2222-222222222222}&ObjectId={33333333-3333-3333-3333-333333333333}"
+        )]),
+        ["ObjectIdNotInMft"]
+    );
 }
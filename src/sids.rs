@@ -0,0 +1,411 @@
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::registry::*;
+use crate::report::*;
+
+const PROFILE_LIST: &str = "Microsoft\\Windows NT\\CurrentVersion\\ProfileList";
+const SAM_ACCOUNT: &str = "SAM\\Domains\\Account";
+const SAM_BUILTIN: &str = "SAM\\Domains\\Builtin";
+const BUILTIN_SID: &str = "S-1-5-32";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SidUser {
+    pub name: Option<String>,
+    pub profile_path: Option<String>,
+}
+
+/// Maps user SIDs to names and profile paths,
+/// loaded from an offline SOFTWARE hive and/or a SID map file.
+#[derive(Default)]
+pub struct SidResolver {
+    users: HashMap<String /*SID*/, SidUser>,
+}
+
+// The user name is the last component of the profile path (C:\Users\alice -> alice).
+fn name_from_profile_path(path: &str) -> Option<String> {
+    path.rsplit(['\\', '/'])
+        .find(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+/// Returns the SID embedded in an item URL,
+/// e.g. winrt://{S-1-5-21-...-1001}/LS/Desktop/ActivityData/ActivityHistory/{GUID}
+pub fn sid_from_item_url(url: &str) -> Option<&str> {
+    let start = url.find("://{")? + 4;
+    let len = url[start..].find('}')?;
+    let sid = &url[start..start + len];
+    if sid.starts_with("S-1-") {
+        Some(sid)
+    } else {
+        None
+    }
+}
+
+/// Parses a binary SID: revision, number of sub-authorities, 48-bit big-endian authority
+/// and little-endian sub-authorities. Returns its string form and length in bytes.
+pub fn parse_sid(b: &[u8]) -> Option<(String, usize)> {
+    if b.len() < 8 || b[0] != 1 || b[1] > 15 {
+        return None;
+    }
+    let len = 8 + b[1] as usize * 4;
+    if b.len() < len {
+        return None;
+    }
+    let authority = b[2..8].iter().fold(0_u64, |a, x| a << 8 | *x as u64);
+    let mut sid = format!("S-1-{authority}");
+    for sub in b[8..len].chunks_exact(4) {
+        sid += &format!("-{}", u32::from_le_bytes([sub[0], sub[1], sub[2], sub[3]]));
+    }
+    Some((sid, len))
+}
+
+// The V value of SAM\Domains\Account ends with the machine SID (S-1-5-21-x-y-z).
+fn machine_sid(v: &[u8]) -> Option<String> {
+    let (sid, _) = parse_sid(&v[v.len().checked_sub(24)?..])?;
+    if sid.starts_with("S-1-5-21-") {
+        Some(sid)
+    } else {
+        None
+    }
+}
+
+/// Parses a value made of consecutive binary SIDs, None when it is anything else.
+pub fn parse_sid_list(mut b: &[u8]) -> Option<Vec<String>> {
+    let mut sids = Vec::new();
+    while !b.is_empty() {
+        let (sid, len) = parse_sid(b)?;
+        sids.push(sid);
+        b = &b[len..];
+    }
+    Some(sids)
+}
+
+impl SidResolver {
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    pub fn resolve(&self, sid: &str) -> Option<&SidUser> {
+        self.users.get(&sid.to_uppercase())
+    }
+
+    /// SID of an account name, with or without its domain (DESKTOP-1\alice),
+    /// None when no account or more than one has that name.
+    pub fn find_sid(&self, account: &str) -> Option<&str> {
+        let name = account.rsplit('\\').next()?;
+        let mut found = self.users.iter().filter(|(_, u)| {
+            u.name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        });
+        let (sid, _) = found.next()?;
+        found.next().is_none().then_some(sid.as_str())
+    }
+
+    fn add(&mut self, sid: &str, user: SidUser) {
+        let entry = self.users.entry(sid.to_uppercase()).or_default();
+        if user.name.is_some() {
+            entry.name = user.name;
+        }
+        if user.profile_path.is_some() {
+            entry.profile_path = user.profile_path;
+        }
+        if entry.name.is_none() {
+            entry.name = entry.profile_path.as_deref().and_then(name_from_profile_path);
+        }
+    }
+
+    /// Reads ProfileList\<SID>\ProfileImagePath from an offline SOFTWARE hive.
+    /// Returns the number of profiles found.
+    pub fn load_profile_list(&mut self, software_hive: &Path) -> Result<usize, SimpleError> {
+        let hive = RegHive::open(software_hive)?;
+        let root = hive.root()?;
+        let profile_list = root.subkey_path(PROFILE_LIST).ok_or_else(|| {
+            SimpleError::new(format!(
+                "Can't find {PROFILE_LIST} in '{}'",
+                software_hive.to_string_lossy()
+            ))
+        })?;
+        let mut count = 0;
+        for key in profile_list.subkeys() {
+            if let Some(path) = key.value("ProfileImagePath") {
+                self.add(
+                    &key.name(),
+                    SidUser {
+                        name: None,
+                        profile_path: Some(path.as_string()),
+                    },
+                );
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Reads the local users (Domains\Account\Users\Names) and groups
+    /// (Domains\Builtin\Aliases\Names) from an offline SAM hive; the RID of each name is
+    /// the type of its default value. Returns the number of accounts found.
+    pub fn load_sam(&mut self, sam_hive: &Path) -> Result<usize, SimpleError> {
+        let hive = RegHive::open(sam_hive)?;
+        let root = hive.root()?;
+        let account = root.subkey_path(SAM_ACCOUNT).ok_or_else(|| {
+            SimpleError::new(format!(
+                "Can't find {SAM_ACCOUNT} in '{}'",
+                sam_hive.to_string_lossy()
+            ))
+        })?;
+        let sid = account
+            .value("V")
+            .and_then(|v| machine_sid(&v.data))
+            .ok_or_else(|| {
+                SimpleError::new(format!(
+                    "Can't find the machine SID in '{}'",
+                    sam_hive.to_string_lossy()
+                ))
+            })?;
+        let mut count = self.add_sam_names(account, "Users\\Names", &sid);
+        if let Some(builtin) = root.subkey_path(SAM_BUILTIN) {
+            count += self.add_sam_names(builtin, "Aliases\\Names", BUILTIN_SID);
+        }
+        Ok(count)
+    }
+
+    fn add_sam_names(&mut self, domain: RegKey, path: &str, domain_sid: &str) -> usize {
+        let mut count = 0;
+        for key in domain.subkey_path(path).map(|k| k.subkeys()).unwrap_or_default() {
+            if let Some(rid) = key.value("") {
+                self.add(
+                    &format!("{domain_sid}-{}", rid.typ),
+                    SidUser {
+                        name: Some(key.name()),
+                        profile_path: None,
+                    },
+                );
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Reads a CSV SID map: `SID,Name[,ProfilePath]` per line, lines starting with '#' are ignored.
+    /// Returns the number of SIDs found.
+    pub fn load_sid_map(&mut self, sid_map: &Path) -> Result<usize, SimpleError> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .comment(Some(b'#'))
+            .trim(csv::Trim::All)
+            .from_path(sid_map)
+            .map_err(|e| {
+                SimpleError::new(format!("Can't read SID map '{}': {e}", sid_map.to_string_lossy()))
+            })?;
+        let mut count = 0;
+        for record in rdr.records() {
+            let record = record.map_err(|e| SimpleError::new(format!("{e}")))?;
+            let sid = match record.get(0) {
+                // skip the header line, if any
+                Some(sid) if sid.to_uppercase().starts_with("S-1-") => sid,
+                _ => continue,
+            };
+            let field = |i| record.get(i).filter(|s: &&str| !s.is_empty()).map(String::from);
+            self.add(
+                sid,
+                SidUser {
+                    name: field(1),
+                    profile_path: field(2),
+                },
+            );
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Sets the columns `insert_sid` writes, for the CSV header of reports
+    /// where only some rows have the SID.
+    pub fn set_sid_fields(&self, r: &dyn Report, prefix: &str) {
+        r.set_field(&format!("{prefix}SID"));
+        if !self.is_empty() {
+            r.set_field(&format!("{prefix}Name"));
+            r.set_field(&format!("{prefix}ProfilePath"));
+        }
+    }
+
+    /// Writes `<prefix>SID`, and `<prefix>Name`/`<prefix>ProfilePath` when SIDs were loaded.
+    pub fn insert_sid(&self, r: &dyn Report, prefix: &str, sid: &str) {
+        r.insert_str_val(&format!("{prefix}SID"), sid.to_string());
+        if self.is_empty() {
+            return;
+        }
+        let user = self.resolve(sid).cloned().unwrap_or_default();
+        r.insert_str_val(&format!("{prefix}Name"), user.name.unwrap_or_default());
+        r.insert_str_val(
+            &format!("{prefix}ProfilePath"),
+            user.profile_path.unwrap_or_default(),
+        );
+    }
+
+    /// Sets the columns `insert_sid_list` writes.
+    pub fn set_sid_list_fields(&self, r: &dyn Report, prefix: &str) {
+        r.set_field(&format!("{prefix}SIDs"));
+        if !self.is_empty() {
+            r.set_field(&format!("{prefix}Names"));
+        }
+    }
+
+    /// Writes the lists `<prefix>SIDs`, and `<prefix>Names` when SIDs were loaded.
+    pub fn insert_sid_list(&self, r: &dyn Report, prefix: &str, sids: &[String]) {
+        r.insert_str_list_val(&format!("{prefix}SIDs"), sids.to_vec());
+        if self.is_empty() {
+            return;
+        }
+        let names: Vec<String> = sids
+            .iter()
+            .map(|sid| self.resolve(sid).and_then(|u| u.name.clone()).unwrap_or_default())
+            .collect();
+        r.insert_str_list_val(&format!("{prefix}Names"), names);
+    }
+}
+
+#[test]
+fn test_sid_from_item_url() {
+    assert_eq!(
+        sid_from_item_url("winrt://{S-1-5-21-2658465553-4118227829-2389125212-1001}/LS/Desktop/ActivityData/ActivityHistory/{2C7DDE0F-0C21-4B60-9B44-5C3D1DFD4C18}"),
+        Some("S-1-5-21-2658465553-4118227829-2389125212-1001")
+    );
+    assert_eq!(sid_from_item_url("file:///C:/Users/test/Desktop/a.txt"), None);
+    assert_eq!(sid_from_item_url("winrt://{2C7DDE0F-0C21-4B60}/LS"), None);
+    assert_eq!(sid_from_item_url("winrt://{S-1-5-21"), None);
+}
+
+#[test]
+fn test_load_profile_list() {
+    use crate::registry::tests::*;
+
+    let mut b = HiveBuilder::default();
+    let path = b.add_value("ProfileImagePath", REG_EXPAND_SZ, &utf16z("C:\\Users\\alice"));
+    let user = b.add_key("S-1-5-21-1-2-3-1001", &[], &[path]);
+    let path = b.add_value(
+        "ProfileImagePath",
+        REG_EXPAND_SZ,
+        &utf16z("%systemroot%\\system32\\config\\systemprofile"),
+    );
+    let system = b.add_key("S-1-5-18", &[], &[path]);
+    let profile_list = b.add_key("ProfileList", &[user, system], &[]);
+    let current_version = b.add_key("CurrentVersion", &[profile_list], &[]);
+    let windows_nt = b.add_key("Windows NT", &[current_version], &[]);
+    let microsoft = b.add_key("Microsoft", &[windows_nt], &[]);
+    let root = b.add_key("ROOT", &[microsoft], &[]);
+
+    let dir = tempdir::TempDir::new("sidr_test").unwrap();
+    let software = dir.path().join("SOFTWARE");
+    std::fs::write(&software, b.build(root)).unwrap();
+    let sid_map = dir.path().join("sids.csv");
+    std::fs::write(
+        &sid_map,
+        "# exported from AD\nSID,Name\ns-1-5-21-1-2-3-1001, CORP\\alice\nS-1-5-21-1-2-3-1002,bob,C:\\Users\\bob\n",
+    )
+    .unwrap();
+
+    let mut sids = SidResolver::default();
+    assert_eq!(sids.load_profile_list(&software).unwrap(), 2);
+    assert_eq!(
+        sids.resolve("S-1-5-21-1-2-3-1001"),
+        Some(&SidUser {
+            name: Some("alice".to_string()),
+            profile_path: Some("C:\\Users\\alice".to_string())
+        })
+    );
+    assert_eq!(sids.load_sid_map(&sid_map).unwrap(), 2);
+    assert_eq!(
+        sids.resolve("S-1-5-21-1-2-3-1001").unwrap().name.as_deref(),
+        Some("CORP\\alice")
+    );
+    assert_eq!(
+        sids.resolve("S-1-5-21-1-2-3-1002").unwrap().profile_path.as_deref(),
+        Some("C:\\Users\\bob")
+    );
+    assert_eq!(sids.resolve("S-1-5-21-1-2-3-1003"), None);
+}
+
+#[test]
+fn test_parse_sid() {
+    let system = [1, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0];
+    assert_eq!(parse_sid(&system), Some(("S-1-5-18".to_string(), 12)));
+    let mut user = vec![1, 5, 0, 0, 0, 0, 0, 5];
+    for sub in [21_u32, 1, 2, 3, 1001] {
+        user.extend(sub.to_le_bytes());
+    }
+    let mut both = user.clone();
+    both.extend(system);
+    assert_eq!(
+        parse_sid_list(&both),
+        Some(vec!["S-1-5-21-1-2-3-1001".to_string(), "S-1-5-18".to_string()])
+    );
+    assert_eq!(parse_sid_list(&user[..20]), None);
+    assert_eq!(parse_sid(b"S-1-5-18"), None);
+}
+
+#[test]
+fn test_load_sam() {
+    use crate::registry::tests::*;
+
+    let mut b = HiveBuilder::default();
+    let rid = b.add_value("", 1001, &[]);
+    let alice = b.add_key("alice", &[], &[rid]);
+    let rid = b.add_value("", 500, &[]);
+    let admin = b.add_key("Administrator", &[], &[rid]);
+    let names = b.add_key("Names", &[alice, admin], &[]);
+    let users = b.add_key("Users", &[names], &[]);
+    let mut v = vec![0u8; 0x40];
+    v.extend([1, 4, 0, 0, 0, 0, 0, 5]);
+    for sub in [21_u32, 1, 2, 3] {
+        v.extend(sub.to_le_bytes());
+    }
+    let v = b.add_value("V", 3, &v);
+    let account = b.add_key("Account", &[users], &[v]);
+    let rid = b.add_value("", 544, &[]);
+    let admins = b.add_key("Administrators", &[], &[rid]);
+    let names = b.add_key("Names", &[admins], &[]);
+    let aliases = b.add_key("Aliases", &[names], &[]);
+    let builtin = b.add_key("Builtin", &[aliases], &[]);
+    let domains = b.add_key("Domains", &[account, builtin], &[]);
+    let sam = b.add_key("SAM", &[domains], &[]);
+    let root = b.add_key("ROOT", &[sam], &[]);
+
+    let dir = tempdir::TempDir::new("sidr_test").unwrap();
+    let path = dir.path().join("SAM");
+    std::fs::write(&path, b.build(root)).unwrap();
+
+    let mut sids = SidResolver::default();
+    assert_eq!(sids.load_sam(&path).unwrap(), 3);
+    assert_eq!(
+        sids.resolve("S-1-5-21-1-2-3-1001").unwrap().name.as_deref(),
+        Some("alice")
+    );
+    assert_eq!(
+        sids.resolve("S-1-5-21-1-2-3-500").unwrap().name.as_deref(),
+        Some("Administrator")
+    );
+    assert_eq!(
+        sids.resolve("S-1-5-32-544").unwrap().name.as_deref(),
+        Some("Administrators")
+    );
+}
+
+#[test]
+fn test_find_sid() {
+    let mut sids = SidResolver::default();
+    let user = |name: &str| SidUser {
+        name: Some(name.to_string()),
+        profile_path: None,
+    };
+    sids.add("S-1-5-21-1-2-3-1001", user("alice"));
+    sids.add("S-1-5-21-1-2-3-1002", user("bob"));
+    sids.add("S-1-5-21-4-5-6-1002", user("bob"));
+    assert_eq!(sids.find_sid("DESKTOP-1\\Alice"), Some("S-1-5-21-1-2-3-1001"));
+    assert_eq!(sids.find_sid("alice"), Some("S-1-5-21-1-2-3-1001"));
+    assert_eq!(sids.find_sid("DESKTOP-1\\bob"), None);
+    assert_eq!(sids.find_sid("carol"), None);
+}
@@ -1,10 +1,14 @@
+use chrono::prelude::*;
 use itertools::Itertools;
 use simple_error::SimpleError;
 use std::collections::HashMap;
 use std::path::Path;
 
+use crate::classify::*;
+use crate::properties::*;
 use crate::report::*;
 use crate::shared::*;
+use crate::sids::*;
 use crate::utils::*;
 
 use ese_parser_lib::ese_parser::FromBytes;
@@ -44,7 +48,7 @@ fn sqlite_get_hostname(c: &sqlite::Connection) -> Result<String, SimpleError> {
 fn populate_property_id_maps<'a>(
     c: &sqlite::Connection,
     idToProp: &'a mut HashMap<i64, (String, i64)>,
-    NameToId: &'a mut HashMap<String, i64>,
+    NameToId: &'a mut PropertyResolver<i64>,
 ) -> Result<(), SimpleError> {
     let q = "select Id, Name, StorageType from SystemIndex_1_PropertyStore_Metadata";
     let s = map_err!(c.prepare(q))?;
@@ -54,25 +58,82 @@ fn populate_property_id_maps<'a>(
         let name = row.read::<&str, _>("Name").to_string();
         let storageType = row.read::<i64, _>("StorageType");
 
-        idToProp.insert(id, (name.clone(), storageType));
-        NameToId.insert(name, id);
+        idToProp.insert(id, (name, storageType));
     }
+    // ColumnIds differ between Windows builds, properties are looked up by name
+    *NameToId =
+        PropertyResolver::new(idToProp.iter().map(|(id, (name, _))| (name.as_str(), *id)));
     Ok(())
 }
 
+// Columns of the properties found in the property store, in ColumnId order, as written by
+// write_record_to_report. Set on every row so that the CSV header has all of them.
+fn sqlite_property_columns(
+    c: &sqlite::Connection,
+    idToProp: &HashMap<i64, (String, i64)>,
+) -> Result<Vec<String>, SimpleError> {
+    let q = "select distinct ColumnId from SystemIndex_1_PropertyStore order by ColumnId";
+    let s = map_err!(c.prepare(q))?;
+    let mut columns = Vec::new();
+    for row in s.into_iter() {
+        let id = map_err!(row)?.read::<i64, _>("ColumnId");
+        if let Some((name, 11..=13)) = idToProp.get(&id) {
+            columns.push(name.replace('.', "_"));
+        }
+    }
+    Ok(columns)
+}
+
+// SDID of every document, from the gather table of the database next to the property store
+// (Windows-gather.db); its DocumentID is the WorkId of the property store.
+fn sqlite_gather_sdids(f: &Path) -> Result<HashMap<u32, u32>, SimpleError> {
+    let stem = f.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let gather = f.with_file_name(format!("{stem}-gather.db"));
+    if !gather.is_file() {
+        return Ok(HashMap::new());
+    }
+    let c = map_err!(sqlite::Connection::open_with_flags(
+        &gather,
+        sqlite::OpenFlags::new().with_read_only()
+    ))?;
+    let s = map_err!(c.prepare("select DocumentID, SDID from SystemIndex_Gthr"))?;
+    let mut sdids = HashMap::new();
+    for row in s.into_iter().flatten() {
+        let doc_id = row.try_read::<Option<i64>, _>("DocumentID");
+        let sdid = row.try_read::<Option<i64>, _>("SDID");
+        if let (Ok(Some(d)), Ok(Some(s))) = (doc_id, sdid) {
+            sdids.insert(d as u32, s as u32);
+        }
+    }
+    Ok(sdids)
+}
+
 // This report will provide information about all the files that have been indexed by Windows search,
 // including the file name, path, and creation/modification dates.
 pub fn sqlite_generate_report(
     f: &Path,
+    archive_src: Option<&str>,
     report_prod: &ReportProducer,
+    opts: &DumpOptions,
     status_logger: &mut Box<dyn Write>,
 ) -> Result<(), SimpleError> {
     writeln!(
         status_logger,
         "Processing SQLite db: {}",
-        &f.to_string_lossy()
+        archive_src.unwrap_or(&f.to_string_lossy())
     )
     .map_err(|e| SimpleError::new(format!("{e}")))?;
+    // per-user databases are named after the user's SID (S-1-5-21-...-1001.db)
+    let db_owner = DbOwner::of_database(f, &opts.sids);
+    let db_owner_sid = db_owner.as_ref().map(|o| o.sid.clone());
+    let db_user = db_owner
+        .as_ref()
+        .and_then(|o| o.name.clone())
+        .filter(|name| !name.is_empty());
+    if let Some(user) = db_user {
+        writeln!(status_logger, "Database of user {user}")
+            .map_err(|e| SimpleError::new(format!("{e}")))?;
+    }
 
     let c = map_err!(sqlite::Connection::open_with_flags(
         f,
@@ -89,28 +150,89 @@ pub fn sqlite_generate_report(
         }
     };
 
-    let (mut file_rep, mut ie_rep, mut act_rep) =
-        init_reports(f, report_prod, &recovered_hostname, status_logger, None)?;
+    let sdids = sqlite_gather_sdids(f).unwrap_or_else(|e| {
+        eprintln!("Could not read the SDIDs of the gather table: {e}");
+        HashMap::new()
+    });
+    let secstore = load_secstore(f, status_logger);
 
+    let mut report_set = ReportSet::new(
+        f,
+        archive_src,
+        db_owner,
+        report_prod,
+        &recovered_hostname,
+        status_logger,
+        None,
+    )?;
 
     let mut idToProp = HashMap::<i64, (String, i64)>::new();
-    let mut propNameToId = HashMap::<String, i64>::new();
+    let mut propNameToId = PropertyResolver::<i64>::new([]);
     if populate_property_id_maps(&c, &mut idToProp, &mut propNameToId).is_err() {
         panic!("Unable to read property IDs.")
     };
+    let columns = sqlite_property_columns(&c, &idToProp)?;
 
-    let mut handler = |workId: u32, record: &mut HashMap<i64, Vec<u8>>| {
+    let mut handler = |workId: u32, record: &mut HashMap<i64, Vec<u8>>| -> Result<(), SimpleError> {
         // new WorkId, handle all collected fields
         if !record.is_empty() {
-            if is_internet_record(&record, &propNameToId).is_ok() {
-                write_record_to_report(record, workId, &idToProp, &mut *ie_rep);
-            } else if is_activity_history_record(record, &propNameToId).is_ok() {
-                write_record_to_report(record, workId, &idToProp, &mut *act_rep);
+            let rec = SqliteRecord {
+                record,
+                idToProp: &idToProp,
+                propNameToId: &propNameToId,
+            };
+            let hostname = rec.get_str("System.ComputerName");
+            let category = opts.classifier.classify(&|name| rec.get_str(name));
+            let rep = report_set.get(hostname.as_deref(), category, status_logger)?;
+            if let Some(layout) = report_layout(category) {
+                write_property_row(&mut **rep, workId, &rec, layout);
+                if category == ACTIVITY_HISTORY {
+                    insert_activity_fields(&**rep, &rec, &opts.sids);
+                    // activities of a per-user database without a SID in their URL are its owner's
+                    let url = rec.get_str("System.ItemUrl");
+                    let url_sid = url.as_deref().and_then(sid_from_item_url);
+                    if let (Some(sid), None) = (&db_owner_sid, url_sid) {
+                        opts.sids.insert_sid(&**rep, "User", sid);
+                    }
+                    insert_timeline_fields(&**rep, &rec, &opts.timeline);
+                    record_app_usage(&mut report_set, hostname.as_deref(), &rec);
+                }
             } else {
-                write_record_to_report(record, workId, &idToProp, &mut *file_rep);
+                write_record_to_report(record, workId, &idToProp, &columns, &mut **rep);
+                if category == INTERNET_HISTORY {
+                    if let Some(url) = rec.get_str("System.ItemUrl") {
+                        insert_browser_fields(&**rep, &url);
+                    }
+                } else {
+                    insert_file_fields(&**rep, &rec, &opts.sids);
+                    insert_mount_fields(&**rep, &rec, &opts.mounts);
+                    if !sdids.is_empty() {
+                        let sdid = sdids.get(&workId).copied();
+                        insert_sdid_fields(&**rep, sdid, &secstore, &opts.sids);
+                    }
+                    write_file_extras(
+                        &mut report_set,
+                        hostname.as_deref(),
+                        workId,
+                        &rec,
+                        &opts.sids,
+                        status_logger,
+                    )?;
+                }
+            }
+            if let (Some(mft), FILE_REPORT | ACTIVITY_HISTORY) = (&opts.mft, category) {
+                write_mft_comparison(
+                    &mut report_set,
+                    hostname.as_deref(),
+                    workId,
+                    &rec,
+                    mft,
+                    status_logger,
+                )?;
             }
             record.clear();
         }
+        Ok(())
     };
 
     let mut record = HashMap::new();
@@ -118,7 +240,7 @@ pub fn sqlite_generate_report(
     while let Ok(State::Row) = s.next() {
         let workId = map_err!(s.read::<i64, _>("WorkId"))? as u32;
         if workId_current != workId {
-            handler(workId_current, &mut record);
+            handler(workId_current, &mut record)?;
             workId_current = workId;
         }
         let columnId = map_err!(s.read::<i64, _>("ColumnId"))?;
@@ -127,8 +249,9 @@ pub fn sqlite_generate_report(
     }
     // handle last element
     if !record.is_empty() {
-        handler(workId_current, &mut record);
+        handler(workId_current, &mut record)?;
     }
+    report_set.finish(status_logger)?;
     Ok(())
 }
 
@@ -136,10 +259,14 @@ fn write_record_to_report(
     record: &HashMap<i64, Vec<u8>>,
     workId: u32,
     idToProp: &HashMap<i64, (String, i64)>,
+    columns: &[String],
     report: &mut dyn Report,
 ) {
     report.create_new_row(false);
     report.insert_int_val("WorkId", workId as u64);
+    for col in columns {
+        report.set_field(col);
+    }
 
     for (col, val) in record.iter().sorted() {
         let property_name = idToProp.get(col);
@@ -162,48 +289,78 @@ fn write_record_to_report(
                         report.insert_int_val(&property_name, u64::from_bytes(val))
                     }
                 }
+                13 => {
+                    // multi-valued string, NUL separated UTF-16
+                    report.insert_str_list_val(&property_name, split_utf16_list(val))
+                }
                 _ => { /* Storage type not supported. */ }
             }
         }
     }
 }
 
-fn is_internet_record(
-    record: &HashMap<i64 /*ColumnId*/, Vec<u8> /*Value*/>,
-    propNameToId: &HashMap<String, i64>,
-) -> Result<(), SimpleError> {
-    let targetUriId = propNameToId
-        .get("System.Link.TargetUrl")
-        .ok_or_else(|| SimpleError::new("Could not find System.Link.TargetUrl ID in map."))?;
-    let targetUriVal = record
-        .get(targetUriId)
-        .ok_or_else(|| SimpleError::new("Could not find System.Link.TargetUrl field in record."))?;
-    let uriValStr = String::from_utf8_lossy(targetUriVal).into_owned();
-    if !(uriValStr.starts_with("http")) {
-        return Err(SimpleError::new(
-            "System.Link.TargetUrl does not start with http.",
-        ));
+// Values of a record of SystemIndex_1_PropertyStore, decoded by the StorageType of the property.
+struct SqliteRecord<'a> {
+    record: &'a HashMap<i64 /*ColumnId*/, Vec<u8> /*Value*/>,
+    idToProp: &'a HashMap<i64, (String, i64)>,
+    propNameToId: &'a PropertyResolver<i64>,
+}
+
+impl SqliteRecord<'_> {
+    fn get(&self, name: &str) -> Option<(&[u8], i64 /*StorageType*/)> {
+        let id = self.propNameToId.resolve(name)?;
+        let val = self.record.get(id)?;
+        let (_, storage_type) = self.idToProp.get(id)?;
+        Some((val.as_slice(), *storage_type))
+    }
+
+    // INTEGER and REAL values are read as their text
+    fn get_text<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name)
+            .and_then(|(v, _)| std::str::from_utf8(v).ok()?.parse().ok())
     }
-    Ok(())
 }
 
-fn is_activity_history_record(
-    record: &HashMap<i64 /*ColumnId*/, Vec<u8> /*Value*/>,
-    propNameToId: &HashMap<String, i64>,
-) -> Result<(), SimpleError> {
-    let itemTypeId = propNameToId
-        .get("System.ItemType")
-        .ok_or_else(|| SimpleError::new("Could not find System.ItemType ID in map."))?;
-    let itemTypeVal = record
-        .get(itemTypeId)
-        .ok_or_else(|| SimpleError::new("Could not find System.ItemType field in record."))?;
-    let itemTypeStr = String::from_utf8_lossy(itemTypeVal).into_owned();
-    if itemTypeStr != "ActivityHistoryItem" {
-        return Err(SimpleError::new(
-            "System.ItemType is not ActivityHistoryItem.",
-        ));
+impl PropertyRecord for SqliteRecord<'_> {
+    fn get_str(&self, name: &str) -> Option<String> {
+        self.get(name).map(|(v, storage_type)| match storage_type {
+            13 => split_utf16_list(v).join(LIST_SEPARATOR),
+            _ => String::from_utf8_lossy(v).into_owned(),
+        })
+    }
+
+    fn get_str_list(&self, name: &str) -> Option<Vec<String>> {
+        self.get(name).map(|(v, storage_type)| match storage_type {
+            13 => split_utf16_list(v),
+            _ => vec![String::from_utf8_lossy(v).into_owned()],
+        })
+    }
+
+    fn get_datetime(&self, name: &str) -> Option<DateTime<Utc>> {
+        match self.get(name)? {
+            (v, 12) if v.len() == 8 => Some(get_date_time_from_filetime(u64::from_bytes(v))),
+            _ => None,
+        }
+    }
+
+    fn get_int(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            (v, 12) if v.len() == 8 => Some(i64::from_bytes(v)),
+            _ => self.get_text(name),
+        }
+    }
+
+    fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get_int(name).map(|v| v != 0)
+    }
+
+    fn get_float(&self, name: &str) -> Option<f64> {
+        self.get_text(name)
+    }
+
+    fn get_bytes(&self, name: &str) -> Option<Vec<u8>> {
+        self.get(name).map(|(v, _)| v.to_vec())
     }
-    Ok(())
 }
 
 #[test]
@@ -215,7 +372,7 @@ fn test_get_property_id_map() {
     ))
     .unwrap();
     let mut idToProp = HashMap::<i64, (String, i64)>::new();
-    let mut PropNameToId = HashMap::<String, i64>::new();
+    let mut PropNameToId = PropertyResolver::<i64>::new([]);
     populate_property_id_maps(&c, &mut idToProp, &mut PropNameToId).unwrap();
     assert!(idToProp.len() == 597);
     assert!(PropNameToId.len() == idToProp.len());
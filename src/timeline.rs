@@ -0,0 +1,303 @@
+use chrono::prelude::*;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::utils::*;
+
+// Windows Timeline: ConnectedDevicesPlatform/<account>/ActivitiesCache.db, one per user.
+// Its Activity table holds the activities of which Windows Search indexes a subset.
+
+// Start times closer than this belong to the same activity.
+const START_TIME_TOLERANCE_SECONDS: i64 = 2;
+
+/// An entry of the Activity table of ActivitiesCache.db.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimelineActivity {
+    pub database: String,
+    pub id: Option<String>,
+    /// Applications of the AppId JSON, as `activity_app_ids` gives them.
+    pub app_ids: Vec<String>,
+    pub app_activity_id: Option<String>,
+    pub activity_type: Option<i64>,
+    pub activity_status: Option<i64>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub last_modified_time: Option<DateTime<Utc>>,
+    pub expiration_time: Option<DateTime<Utc>>,
+    pub platform_device_id: Option<String>,
+    pub is_local_only: Option<bool>,
+    pub payload: TimelinePayload,
+}
+
+/// Fields of the Payload JSON.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimelinePayload {
+    pub display_text: Option<String>,
+    pub description: Option<String>,
+    pub app_display_name: Option<String>,
+    pub content_uri: Option<String>,
+    pub active_duration_seconds: Option<i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimelineMatch {
+    /// Same activity ID.
+    Id,
+    /// Same application and AppActivityId or content URI.
+    Content,
+    /// Same application, started at the same time.
+    Time,
+}
+
+impl TimelineMatch {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Id => "Id",
+            Self::Content => "Content",
+            Self::Time => "Time",
+        }
+    }
+}
+
+/// Activities of every ActivitiesCache.db found in the input, by ID and application.
+#[derive(Default)]
+pub struct Timeline {
+    activities: Vec<TimelineActivity>,
+    by_id: HashMap<String, usize>,
+    by_app: HashMap<String, Vec<usize>>,
+}
+
+/// Activity in the Activity History report.
+pub struct IndexedActivity<'a> {
+    pub id: Option<&'a str>,
+    pub app_id: Option<&'a str>,
+    pub app_activity_id: Option<&'a str>,
+    pub content_uri: Option<&'a str>,
+    pub start_time: Option<DateTime<Utc>>,
+}
+
+pub fn activity_type_to_string(activity_type: i64) -> String {
+    match activity_type {
+        2 => "Notification".to_string(),
+        3 => "Mobile Device Backup".to_string(),
+        5 => "Open App/File/Page".to_string(),
+        6 => "App In Use/Focus".to_string(),
+        10 => "Clipboard Text".to_string(),
+        11 | 12 | 15 => "System".to_string(),
+        16 => "Copy/Paste".to_string(),
+        t => t.to_string(),
+    }
+}
+
+pub fn is_timeline_database(p: &Path) -> bool {
+    let is_cache = p
+        .file_name()
+        .and_then(|s| s.to_str())
+        .is_some_and(|name| name.eq_ignore_ascii_case("ActivitiesCache.db"));
+    let in_platform_dir = p
+        .parent()
+        .and_then(|account| account.parent())
+        .and_then(|d| d.file_name())
+        .and_then(|s| s.to_str())
+        .is_some_and(|name| name.eq_ignore_ascii_case("ConnectedDevicesPlatform"));
+    is_cache && in_platform_dir
+}
+
+/// ActivitiesCache.db files under a directory.
+pub fn find_timeline_databases(dir: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .map(|e| e.into_path())
+        .filter(|p| is_timeline_database(p))
+        .collect()
+}
+
+fn unix_time(secs: Option<i64>) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(secs.filter(|s| *s > 0)?, 0).single()
+}
+
+pub fn parse_payload(payload: &str) -> TimelinePayload {
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(payload) else {
+        return TimelinePayload::default();
+    };
+    let text = |key: &str| v[key].as_str().filter(|s| !s.is_empty()).map(String::from);
+    TimelinePayload {
+        display_text: text("displayText"),
+        description: text("description"),
+        app_display_name: text("appDisplayName"),
+        content_uri: text("contentUri"),
+        active_duration_seconds: v["activeDurationSeconds"].as_i64(),
+    }
+}
+
+// Text columns are TEXT in some builds and BLOB in others.
+fn read_text(row: &sqlite::Row, col: &str) -> Option<String> {
+    if let Ok(s) = row.try_read::<Option<&str>, _>(col) {
+        return s.map(String::from);
+    }
+    let bytes = row.try_read::<Option<&[u8]>, _>(col).ok()??;
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+impl Timeline {
+    pub fn from_activities(activities: Vec<TimelineActivity>) -> Self {
+        let mut timeline = Timeline::default();
+        for a in activities {
+            timeline.add(a);
+        }
+        timeline
+    }
+
+    fn add(&mut self, a: TimelineActivity) {
+        let idx = self.activities.len();
+        if let Some(id) = &a.id {
+            self.by_id.insert(id.to_uppercase(), idx);
+        }
+        for app in &a.app_ids {
+            self.by_app.entry(app.to_lowercase()).or_default().push(idx);
+        }
+        self.activities.push(a);
+    }
+
+    /// Reads the Activity table of an ActivitiesCache.db.
+    pub fn load(&mut self, f: &Path) -> Result<(), SimpleError> {
+        self.load_as(f, &f.to_string_lossy())
+    }
+
+    /// Reads an ActivitiesCache.db extracted from `database`, recorded as TimelineDatabase.
+    pub fn load_as(&mut self, f: &Path, database: &str) -> Result<(), SimpleError> {
+        let c = sqlite::Connection::open_with_flags(f, sqlite::OpenFlags::new().with_read_only())
+            .map_err(|e| SimpleError::new(format!("{e}")))?;
+        let s = c
+            .prepare("select * from Activity")
+            .map_err(|e| SimpleError::new(format!("{e}")))?;
+        let database = database.to_string();
+        for row in s.into_iter().flatten() {
+            let int = |col: &str| row.try_read::<Option<i64>, _>(col).ok().flatten();
+            let id = row
+                .try_read::<Option<&[u8]>, _>("Id")
+                .ok()
+                .flatten()
+                .and_then(format_guid);
+            self.add(TimelineActivity {
+                database: database.clone(),
+                id,
+                app_ids: read_text(&row, "AppId")
+                    .map(|a| activity_app_ids(&a))
+                    .unwrap_or_default(),
+                app_activity_id: read_text(&row, "AppActivityId"),
+                activity_type: int("ActivityType"),
+                activity_status: int("ActivityStatus"),
+                start_time: unix_time(int("StartTime")),
+                end_time: unix_time(int("EndTime")),
+                last_modified_time: unix_time(int("LastModifiedTime")),
+                expiration_time: unix_time(int("ExpirationTime")),
+                platform_device_id: read_text(&row, "PlatformDeviceId"),
+                is_local_only: int("IsLocalOnly").map(|v| v != 0),
+                payload: read_text(&row, "Payload")
+                    .map(|p| parse_payload(&p))
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.activities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.activities.is_empty()
+    }
+
+    /// The Timeline entry of an indexed activity: by ID, then by application and
+    /// AppActivityId or content URI, then by application and start time.
+    pub fn find(&self, a: &IndexedActivity) -> Option<(TimelineMatch, &TimelineActivity)> {
+        if let Some(i) = a.id.and_then(|id| self.by_id.get(&id.to_uppercase())) {
+            return Some((TimelineMatch::Id, &self.activities[*i]));
+        }
+        let candidates: Vec<&TimelineActivity> = a
+            .app_id
+            .map(activity_app_ids)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|app| self.by_app.get(&app.to_lowercase()))
+            .flatten()
+            .map(|i| &self.activities[*i])
+            .collect();
+        let same_content = |t: &TimelineActivity| {
+            let same_activity =
+                a.app_activity_id.is_some() && a.app_activity_id == t.app_activity_id.as_deref();
+            let same_uri =
+                a.content_uri.is_some() && a.content_uri == t.payload.content_uri.as_deref();
+            same_activity || same_uri
+        };
+        let same_start = |t: &TimelineActivity| match (a.start_time, t.start_time) {
+            (Some(x), Some(y)) => (x - y).num_seconds().abs() <= START_TIME_TOLERANCE_SECONDS,
+            _ => false,
+        };
+        // an AppActivityId may be reused for every use of a document, prefer the one that started then
+        let by_content = candidates
+            .iter()
+            .filter(|t| same_content(t))
+            .find(|t| same_start(t))
+            .or_else(|| candidates.iter().find(|t| same_content(t)));
+        if let Some(t) = by_content {
+            return Some((TimelineMatch::Content, t));
+        }
+        candidates
+            .into_iter()
+            .find(|t| same_start(t))
+            .map(|t| (TimelineMatch::Time, t))
+    }
+}
+
+#[test]
+fn test_timeline() {
+    let start = Utc.with_ymd_and_hms(2023, 3, 7, 1, 52, 44).unwrap();
+    let payload = r#"{"displayText":"notes.txt","description":"C:\\Users\\alice\\notes.txt","appDisplayName":"Notepad","activeDurationSeconds":42}"#;
+    let notepad = TimelineActivity {
+        id: Some("{6D809377-6AF0-444B-8957-A3773F02200E}".to_string()),
+        app_ids: vec!["{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\notepad.exe".to_string()],
+        app_activity_id: Some("ECB32AF3-1440-4086-94E3-5311F97F89C4\\notes".to_string()),
+        start_time: Some(start),
+        activity_type: Some(5),
+        payload: parse_payload(payload),
+        ..Default::default()
+    };
+    assert_eq!(notepad.payload.app_display_name.as_deref(), Some("Notepad"));
+    assert_eq!(notepad.payload.active_duration_seconds, Some(42));
+    assert_eq!(parse_payload("not json"), TimelinePayload::default());
+    assert_eq!(activity_type_to_string(6), "App In Use/Focus");
+
+    let timeline = Timeline::from_activities(vec![notepad]);
+    let app_id = r#"[{"application":"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\notepad.exe","platform":"windows_win32"}]"#;
+    let indexed = |id, app_activity_id, start_time| IndexedActivity {
+        id,
+        app_id: Some(app_id),
+        app_activity_id,
+        content_uri: None,
+        start_time,
+    };
+    let found = |a: IndexedActivity| timeline.find(&a).map(|(m, _)| m);
+    assert_eq!(
+        found(indexed(Some("{6d809377-6af0-444b-8957-a3773f02200e}"), None, None)),
+        Some(TimelineMatch::Id)
+    );
+    assert_eq!(
+        found(indexed(None, Some("ECB32AF3-1440-4086-94E3-5311F97F89C4\\notes"), None)),
+        Some(TimelineMatch::Content)
+    );
+    assert_eq!(
+        found(indexed(None, None, Some(start + chrono::Duration::seconds(1)))),
+        Some(TimelineMatch::Time)
+    );
+    assert_eq!(found(indexed(None, None, Some(start + chrono::Duration::hours(1)))), None);
+
+    assert!(is_timeline_database(Path::new(
+        "C/Users/alice/AppData/Local/ConnectedDevicesPlatform/L.alice/ActivitiesCache.db"
+    )));
+    assert!(!is_timeline_database(Path::new("C/Users/alice/ActivitiesCache.db")));
+}
@@ -1,6 +1,8 @@
 use chrono::prelude::*;
 
-use bitflags::bitflags;
+use bitflags::{bitflags, Flags};
+use ese_parser_lib::ese_trait::EseDb;
+use simple_error::SimpleError;
 use std::convert::TryInto;
 
 /// Converts a u64 filetime to a DateTime<Utc>
@@ -55,6 +57,143 @@ pub fn find_guid(inp: &str, v: &str) -> String {
     s
 }
 
+// extract a query parameter from a URI like the one above, e.g. KnownFolderId=ThisPCDesktopFolder
+pub fn find_uri_param<'a>(inp: &'a str, name: &str) -> Option<&'a str> {
+    let (_, query) = inp.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|p| p.split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| v)
+}
+
+/// Browser history item, as identified by its System_ItemUrl.
+#[derive(Debug, PartialEq)]
+pub struct BrowserSource<'a> {
+    pub browser: &'a str,
+    pub channel: &'a str,
+    pub profile: &'a str,
+}
+
+const BROWSER_CHANNELS: [&str; 5] = ["stable", "beta", "dev", "canary", "sxs"];
+const BROWSER_CONTAINERS: [&str; 3] = ["history", "recentlyclosed", "quicklinks"];
+
+// Chromium browsers sync their history to the index as
+// winrt://{SID}/LS/Desktop/<browser>/<channel>/<profile>/<History|RecentlyClosed|QuickLinks>/<n>,
+// IE and Edge Legacy as iehistory://{SID}/<url>.
+pub fn parse_browser_item_url(url: &str) -> Option<BrowserSource<'_>> {
+    if url.starts_with("iehistory://") {
+        return Some(BrowserSource {
+            browser: "Internet Explorer",
+            channel: "",
+            profile: "",
+        });
+    }
+    let rest = url.strip_prefix("winrt://{")?;
+    let (_, path) = rest.split_once("}/LS/Desktop/")?;
+    let mut parts = path.split('/');
+    let browser = parts.next()?;
+    let channel = parts.next()?;
+    let profile = parts.next()?;
+    let container = parts.next()?;
+    if browser.is_empty()
+        || profile.is_empty()
+        || !BROWSER_CHANNELS.contains(&channel.to_lowercase().as_str())
+        || !BROWSER_CONTAINERS.contains(&container.to_lowercase().as_str())
+    {
+        return None;
+    }
+    Some(BrowserSource {
+        browser,
+        channel,
+        profile,
+    })
+}
+
+/// Fields of a version 1 (time-based) UUID, as found in NTFS object IDs.
+#[derive(Debug, PartialEq)]
+pub struct UuidV1 {
+    /// Creation time, UUID timestamps count 100ns intervals since 1582-10-15.
+    pub timestamp: DateTime<Utc>,
+    pub clock_sequence: u16,
+    /// MAC address of the machine that generated the UUID.
+    pub node: String,
+}
+
+/// Decodes a GUID string ({57B48856-A0FF-11ED-ACD4-DC215CBBECEC}),
+/// returns None if it is not a version 1 RFC 4122 UUID.
+pub fn decode_uuid_v1(guid: &str) -> Option<UuidV1> {
+    // 100ns intervals between 1582-10-15 and 1601-01-01 (FILETIME epoch)
+    const UUID_EPOCH_TO_FILETIME_EPOCH: u64 = 5_748_192_000_000_000;
+    let hex: String = guid
+        .trim_matches(|c| c == '{' || c == '}')
+        .chars()
+        .filter(|c| *c != '-')
+        .collect();
+    if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let time_low = u64::from_str_radix(&hex[0..8], 16).ok()?;
+    let time_mid = u64::from_str_radix(&hex[8..12], 16).ok()?;
+    let time_hi_and_version = u64::from_str_radix(&hex[12..16], 16).ok()?;
+    let clock_seq = u16::from_str_radix(&hex[16..20], 16).ok()?;
+    if time_hi_and_version >> 12 != 1 || clock_seq >> 14 != 0b10 {
+        return None;
+    }
+    let timestamp = (time_hi_and_version & 0x0FFF) << 48 | time_mid << 32 | time_low;
+    let node = (0..6)
+        .map(|i| &hex[20 + i * 2..22 + i * 2])
+        .collect::<Vec<_>>()
+        .join(":");
+    Some(UuidV1 {
+        timestamp: get_date_time_from_filetime(
+            timestamp.checked_sub(UUID_EPOCH_TO_FILETIME_EPOCH)?,
+        ),
+        clock_sequence: clock_seq & 0x3FFF,
+        node,
+    })
+}
+
+/// Decimal degrees of a GPS coordinate given as degrees, minutes and seconds,
+/// negative for the S and W references.
+pub fn gps_to_decimal(dms: &[f64], reference: Option<&str>) -> Option<f64> {
+    let degrees = dms.first()?
+        + dms.get(1).unwrap_or(&0.0) / 60.0
+        + dms.get(2).unwrap_or(&0.0) / 3600.0;
+    match reference.map(|r| r.trim().to_ascii_uppercase()).as_deref() {
+        Some("S") | Some("W") => Some(-degrees),
+        _ => Some(degrees),
+    }
+}
+
+/// Values of GPS rationals: arrays of u32 numerators and denominators.
+pub fn gps_rationals(numerators: &[u8], denominators: &[u8]) -> Vec<f64> {
+    numerators
+        .chunks_exact(4)
+        .zip(denominators.chunks_exact(4))
+        .map(|(n, d)| {
+            let d = u32::from_le_bytes(d.try_into().unwrap());
+            if d == 0 {
+                0.0
+            } else {
+                u32::from_le_bytes(n.try_into().unwrap()) as f64 / d as f64
+            }
+        })
+        .collect()
+}
+
+/// {XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX} of a 16 bytes GUID, the first three parts little-endian.
+pub fn format_guid(val: &[u8]) -> Option<String> {
+    let b: &[u8; 16] = val.try_into().ok()?;
+    Some(format!(
+        "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        u16::from_le_bytes([b[4], b[5]]),
+        u16::from_le_bytes([b[6], b[7]]),
+        b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]
+    ))
+}
+
 pub fn from_utf16(val: &[u8]) -> String {
     let s: Vec<u16> = val
         .chunks_exact(2)
@@ -63,6 +202,32 @@ pub fn from_utf16(val: &[u8]) -> String {
     String::from_utf16_lossy(s.as_slice())
 }
 
+// Only tagged columns (ids from 256 up) can hold several values.
+const FIRST_TAGGED_COLUMN_ID: u32 = 256;
+
+/// All values of an ESE column. `get_column` returns only the first value of a multi-valued
+/// column (System_Author, System_Keywords, recipients); the others are read by their
+/// 1-based index until there are no more.
+pub fn ese_column_values(
+    jdb: &dyn EseDb,
+    table: u64,
+    column_id: u32,
+) -> Result<Vec<Vec<u8>>, SimpleError> {
+    let first = match jdb.get_column(table, column_id)? {
+        Some(v) => v,
+        None => return Ok(Vec::new()),
+    };
+    let mut values = vec![first];
+    if column_id >= FIRST_TAGGED_COLUMN_ID {
+        let mut index = 2;
+        while let Some(v) = jdb.get_column_mv(table, column_id, index)? {
+            values.push(v);
+            index += 1;
+        }
+    }
+    Ok(values)
+}
+
 bitflags! {
     #[derive(Debug)]
     struct file_attributes_flag: u32 {
@@ -90,6 +255,266 @@ bitflags! {
     }
 }
 
+/// An entry of System_ActivityHistory_AppId, a JSON array like
+/// [{"application":"{6D809377-6AF0-444B-8957-A3773F02200E}\\Notepad++\\notepad++.exe","platform":"windows_win32"},
+///  {"application":"Microsoft.Windows.Photos_8wekyb3d8bbwe!App","platform":"windows_universal"}, ...]
+#[derive(Debug, PartialEq)]
+pub struct ActivityApp {
+    pub application: String,
+    pub platform: String,
+}
+
+/// Entries of System_ActivityHistory_AppId with an application, without duplicates.
+/// A value that is not a JSON array is taken as the application itself.
+pub fn parse_activity_app_id(app_id: &str) -> Vec<ActivityApp> {
+    let mut apps: Vec<ActivityApp> = Vec::new();
+    match serde_json::from_str::<serde_json::Value>(app_id) {
+        Ok(serde_json::Value::Array(entries)) => {
+            for e in &entries {
+                let application = e["application"].as_str().unwrap_or_default();
+                let platform = e["platform"].as_str().unwrap_or_default();
+                if !application.is_empty()
+                    && !apps.iter().any(|a| a.application.eq_ignore_ascii_case(application))
+                {
+                    apps.push(ActivityApp {
+                        application: application.to_string(),
+                        platform: platform.to_string(),
+                    });
+                }
+            }
+        }
+        _ if !app_id.is_empty() => apps.push(ActivityApp {
+            application: app_id.to_string(),
+            platform: String::new(),
+        }),
+        _ => {}
+    }
+    apps
+}
+
+/// The entry naming the application best: the executable path, a Win32 or packaged app ID,
+/// otherwise the first one.
+pub fn primary_activity_app(apps: &[ActivityApp]) -> Option<&ActivityApp> {
+    ["x_exe_path", "windows_win32", "windows_universal", "packageId"]
+        .iter()
+        .find_map(|p| apps.iter().find(|a| a.platform == *p))
+        .or_else(|| apps.first())
+}
+
+/// Applications of System_ActivityHistory_AppId.
+pub fn activity_app_ids(app_id: &str) -> Vec<String> {
+    parse_activity_app_id(app_id)
+        .into_iter()
+        .map(|a| a.application)
+        .collect()
+}
+
+// Known folders found at the start of Win32 application paths, with their default locations.
+const KNOWN_FOLDERS: &[(&str, &str)] = &[
+    ("{6D809377-6AF0-444B-8957-A3773F02200E}", "C:\\Program Files"),
+    ("{905E63B6-C1BF-494E-B29C-65B732D3D21A}", "C:\\Program Files"),
+    ("{7C5A40EF-A0FB-4BFC-874A-C0F2E0B9FA8E}", "C:\\Program Files (x86)"),
+    ("{F7F1ED05-9F6D-47A2-AAAE-29D317C6F066}", "C:\\Program Files\\Common Files"),
+    ("{DE974D24-D9C6-4D3E-BF91-F4455120B917}", "C:\\Program Files (x86)\\Common Files"),
+    ("{F38BF404-1D43-42F2-9305-67DE0B28FC23}", "C:\\Windows"),
+    ("{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}", "C:\\Windows\\System32"),
+    ("{D65231B0-B2F1-4857-A4CE-A8E7C6EA7D27}", "C:\\Windows\\SysWOW64"),
+    ("{62AB5D82-FDC1-4DC3-A9DD-070D1D495D97}", "C:\\ProgramData"),
+    ("{0139D44E-6AFE-49F2-8690-3DAFCAE6FFB8}", "C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs"),
+    ("{0762D272-C50A-4BB0-A382-697DCD729B80}", "C:\\Users"),
+    ("{5E6C858F-0E22-4760-9AFE-EA3317B67173}", "%USERPROFILE%"),
+    ("{B4BFCC3A-DB2C-424C-B029-7FE99A87C641}", "%USERPROFILE%\\Desktop"),
+    ("{FDD39AD0-238F-46AF-ADB4-6C85480369C7}", "%USERPROFILE%\\Documents"),
+    ("{374DE290-123F-4565-9164-39C4925E467B}", "%USERPROFILE%\\Downloads"),
+    ("{F1B32785-6FBA-4FCF-9D55-7B8E7F157091}", "%LOCALAPPDATA%"),
+    ("{A520A1A4-1780-4FF6-BD18-167343C5AF16}", "%USERPROFILE%\\AppData\\LocalLow"),
+    ("{3EB685DB-65F9-4CF6-A03A-E3EF65729F3D}", "%APPDATA%"),
+    ("{A77F5D77-2E2B-44C3-A6A2-ABA601054A51}", "%APPDATA%\\Microsoft\\Windows\\Start Menu\\Programs"),
+];
+
+/// Replaces a known folder GUID at the start of a path with the folder's default location:
+/// {6D809377-6AF0-444B-8957-A3773F02200E}\Notepad++\notepad++.exe -> C:\Program Files\Notepad++\notepad++.exe
+pub fn resolve_known_folder(path: &str) -> String {
+    for (guid, folder) in KNOWN_FOLDERS {
+        if path.len() >= guid.len()
+            && path.is_char_boundary(guid.len())
+            && path[..guid.len()].eq_ignore_ascii_case(guid)
+        {
+            return format!("{folder}{}", &path[guid.len()..]);
+        }
+    }
+    path.to_string()
+}
+
+/// Sync root of a cloud file, from System_StorageProviderId:
+/// OneDrive!S-1-5-21-...-1001!Business1|{GUID}
+#[derive(Debug, PartialEq)]
+pub struct StorageProvider<'a> {
+    pub provider: &'a str,
+    pub sid: Option<&'a str>,
+    pub account: Option<&'a str>,
+}
+
+pub fn parse_storage_provider_id(id: &str) -> Option<StorageProvider<'_>> {
+    let id = id.split('|').next()?;
+    let mut parts = id.split('!');
+    let provider = parts.next().filter(|p| !p.is_empty())?;
+    Some(StorageProvider {
+        provider,
+        sid: parts.next().filter(|s| s.starts_with("S-1-")),
+        account: parts.next().filter(|a| !a.is_empty()),
+    })
+}
+
+/// System_StorageProviderState (Windows.Storage.Provider.StorageProviderState)
+pub fn storage_provider_state_to_string(state: i64) -> String {
+    match state {
+        0 => "InSync".to_string(),
+        1 => "Syncing".to_string(),
+        2 => "Paused".to_string(),
+        3 => "Error".to_string(),
+        4 => "Warning".to_string(),
+        5 => "Offline".to_string(),
+        _ => state.to_string(),
+    }
+}
+
+bitflags! {
+    // SYNC_TRANSFER_STATUS
+    #[derive(Debug)]
+    struct sync_transfer_status_flag: u32 {
+        const STS_NEEDSUPLOAD            = 0x00000001;
+        const STS_NEEDSDOWNLOAD          = 0x00000002;
+        const STS_TRANSFERRING           = 0x00000004;
+        const STS_PAUSED                 = 0x00000008;
+        const STS_HASERROR               = 0x00000010;
+        const STS_FETCHING_METADATA      = 0x00000020;
+        const STS_USER_REQUESTED_REFRESH = 0x00000040;
+        const STS_HASWARNING             = 0x00000080;
+        const STS_EXCLUDED               = 0x00000100;
+        const STS_INCOMPLETE             = 0x00000200;
+        const STS_PLACEHOLDER_IFEMPTY    = 0x00000400;
+    }
+}
+
+bitflags! {
+    // PLACEHOLDER_STATES
+    #[derive(Debug)]
+    struct placeholder_states_flag: u32 {
+        const PS_MARKED_FOR_OFFLINE_AVAILABILITY = 0x00000001;
+        const PS_FULL_PRIMARY_STREAM_AVAILABLE   = 0x00000002;
+        const PS_CREATE_FILE_ACCESSIBLE          = 0x00000004;
+        const PS_CLOUDFILE_PLACEHOLDER           = 0x00000008;
+    }
+}
+
+// Names of the set flags joined with " | ", unknown bits in hex: STS_HASERROR | 0x10000
+fn flag_names<F: Flags<Bits = u32>>(flags: F) -> String {
+    let mut names: Vec<String> = flags.iter_names().map(|(name, _)| name.to_string()).collect();
+    let unknown = flags.bits() & !F::all().bits();
+    if unknown != 0 {
+        names.push(format!("{unknown:#x}"));
+    }
+    names.join(" | ")
+}
+
+pub fn sync_transfer_status_to_string(status: u32) -> String {
+    if status == 0 {
+        return "STS_NONE".to_string();
+    }
+    flag_names(sync_transfer_status_flag::from_bits_retain(status))
+}
+
+pub fn placeholder_status_to_string(status: u32) -> String {
+    if status == 0 {
+        return "PS_NONE".to_string();
+    }
+    flag_names(placeholder_states_flag::from_bits_retain(status))
+}
+
+bitflags! {
+    // SFGAO flags of a shell item
+    #[derive(Debug)]
+    struct sfgao_flag: u32 {
+        const SFGAO_CANCOPY        = 0x00000001;
+        const SFGAO_CANMOVE        = 0x00000002;
+        const SFGAO_CANLINK        = 0x00000004;
+        const SFGAO_STORAGE        = 0x00000008;
+        const SFGAO_CANRENAME      = 0x00000010;
+        const SFGAO_CANDELETE      = 0x00000020;
+        const SFGAO_HASPROPSHEET   = 0x00000040;
+        const SFGAO_DROPTARGET     = 0x00000100;
+        const SFGAO_ENCRYPTED      = 0x00002000;
+        const SFGAO_ISSLOW         = 0x00004000;
+        const SFGAO_GHOSTED        = 0x00008000;
+        const SFGAO_LINK           = 0x00010000;
+        const SFGAO_SHARE          = 0x00020000;
+        const SFGAO_READONLY       = 0x00040000;
+        const SFGAO_HIDDEN         = 0x00080000;
+        const SFGAO_NONENUMERATED  = 0x00100000;
+        const SFGAO_NEWCONTENT     = 0x00200000;
+        const SFGAO_STREAM         = 0x00400000;
+        const SFGAO_STORAGEANCESTOR = 0x00800000;
+        const SFGAO_VALIDATE       = 0x01000000;
+        const SFGAO_REMOVABLE      = 0x02000000;
+        const SFGAO_COMPRESSED     = 0x04000000;
+        const SFGAO_BROWSABLE      = 0x08000000;
+        const SFGAO_FILESYSANCESTOR = 0x10000000;
+        const SFGAO_FOLDER         = 0x20000000;
+        const SFGAO_FILESYSTEM     = 0x40000000;
+        const SFGAO_HASSUBFOLDER   = 0x80000000;
+    }
+}
+
+/// Attributes of a shortcut target, from System_Link_TargetSFGAOFlags.
+pub fn sfgao_flags_to_string(flags: u32) -> String {
+    flag_names(sfgao_flag::from_bits_retain(flags))
+}
+
+/// Pin state of a cloud file, from the Pinned, Unpinned and RecallOnDataAccess file attributes.
+pub fn pin_state(attributes: u32) -> &'static str {
+    let f = file_attributes_flag::from_bits_retain(attributes);
+    if f.contains(file_attributes_flag::FILE_ATTRIBUTE_PINNED) {
+        "AlwaysAvailable"
+    } else if f.contains(file_attributes_flag::FILE_ATTRIBUTE_UNPINNED) {
+        "OnlineOnly"
+    } else if f.contains(file_attributes_flag::FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS) {
+        "NotDownloaded"
+    } else {
+        "LocallyAvailable"
+    }
+}
+
+/// Parent folder and name of a Windows path: C:\Users\u\a.txt -> (C:\Users\u, a.txt)
+pub fn split_item_path(path: &str) -> (&str, &str) {
+    match path.rfind('\\') {
+        Some(i) => (&path[..i], &path[i + 1..]),
+        None => ("", path),
+    }
+}
+
+/// Extension of a file name, with the dot: .txt
+pub fn file_extension(name: &str) -> Option<&str> {
+    name.rfind('.').filter(|i| *i > 0).map(|i| &name[i..])
+}
+
+/// User profile a path is in: C:\Users\<name>\...
+pub fn user_profile_from_path(path: &str) -> Option<&str> {
+    let mut parts = path.split('\\');
+    let drive = parts.next()?;
+    if drive.len() != 2 || !drive.ends_with(':') {
+        return None;
+    }
+    if !parts.next()?.eq_ignore_ascii_case("Users") {
+        return None;
+    }
+    parts.next().filter(|name| !name.is_empty())
+}
+
+pub fn file_attributes_flags_to_string(at: u32) -> String {
+    flag_names(file_attributes_flag::from_bits_retain(at))
+}
+
 pub fn file_attributes_to_string(bytes: &Vec<u8>) -> String {
     let at = if bytes.len() == 1 {
         u8::from_le_bytes(bytes[..].try_into().unwrap()) as u32
@@ -100,16 +525,15 @@ pub fn file_attributes_to_string(bytes: &Vec<u8>) -> String {
     } else {
         return format!("{bytes:?}");
     };
-    let f = file_attributes_flag::from_bits_retain(at);
-    format!("{f:?}")
+    file_attributes_flags_to_string(at)
 }
 
 // in: 15F-System_DateModified
 // out: System_DateModified
 pub fn column_string_part(s: &str) -> &str {
     match s.find('-') {
-        Some(i) => &s[i + 1..],
-        None => s,
+        Some(i) if i > 0 && s[..i].chars().all(|c| c.is_ascii_hexdigit()) => &s[i + 1..],
+        _ => s,
     }
 }
 
@@ -117,6 +541,15 @@ pub fn json_escape(input: &str) -> String {
     json::stringify(input)
 }
 
+#[test]
+fn column_string_part_test() {
+    assert_eq!(column_string_part("15F-System_DateModified"), "System_DateModified");
+    assert_eq!(column_string_part("4450-System_ItemType"), "System_ItemType");
+    assert_eq!(column_string_part("System_ItemType"), "System_ItemType");
+    assert_eq!(column_string_part("WorkID"), "WorkID");
+    assert_eq!(column_string_part("-System_Title"), "-System_Title");
+}
+
 #[test]
 fn json_escape_test() {
     let tests = &[
@@ -144,6 +577,146 @@ fn json_escape_test() {
     }
 }
 
+#[test]
+fn decode_uuid_v1_test() {
+    let uuid = decode_uuid_v1("{57B48856-A0FF-11ED-ACD4-DC215CBBECEC}").unwrap();
+    assert_eq!(format_date_time(uuid.timestamp), "2023-01-31T00:36:46.6907222Z");
+    assert_eq!(uuid.clock_sequence, 11476);
+    assert_eq!(uuid.node, "DC:21:5C:BB:EC:EC");
+    // version 4 (random) and malformed GUIDs
+    assert_eq!(decode_uuid_v1("{AC048C6D-1E3C-4B21-B20D-75745DD788B3}"), None);
+    assert_eq!(decode_uuid_v1("{57B48856-A0FF}"), None);
+    assert_eq!(decode_uuid_v1(""), None);
+}
+
+#[test]
+fn parse_browser_item_url_test() {
+    assert_eq!(
+        parse_browser_item_url("winrt://{S-1-5-21-4268361623-692440835-3372367631-1001}/LS/Desktop/Microsoft Edge/stable/Default/History/9"),
+        Some(BrowserSource { browser: "Microsoft Edge", channel: "stable", profile: "Default" })
+    );
+    assert_eq!(
+        parse_browser_item_url("winrt://{S-1-5-21-1-1001}/LS/Desktop/Microsoft Edge/canary/Profile 2/RecentlyClosed/1"),
+        Some(BrowserSource { browser: "Microsoft Edge", channel: "canary", profile: "Profile 2" })
+    );
+    assert_eq!(
+        parse_browser_item_url("winrt://{S-1-5-21-1-1001}/LS/Desktop/Google Chrome/beta/Default/History/3"),
+        Some(BrowserSource { browser: "Google Chrome", channel: "beta", profile: "Default" })
+    );
+    assert_eq!(
+        parse_browser_item_url("iehistory://{S-1-5-21-1-1001}/https://example.com/"),
+        Some(BrowserSource { browser: "Internet Explorer", channel: "", profile: "" })
+    );
+    assert_eq!(
+        parse_browser_item_url("winrt://{S-1-5-21-1-1001}/LS/Desktop/ActivityData/ActivityHistory/{2C7DDE0F-0C21-4B60-9B44-5C3D1DFD4C18}"),
+        None
+    );
+    // the list of profiles, not a profile
+    assert_eq!(
+        parse_browser_item_url("winrt://{S-1-5-21-1-1001}/LS/Desktop/Microsoft Edge/stable/Profiles/Default"),
+        None
+    );
+    assert_eq!(parse_browser_item_url("file:C:/Users/u/Favorites/Bing.url"), None);
+}
+
+#[test]
+fn gps_to_decimal_test() {
+    let lat = gps_to_decimal(&[47.0, 36.0, 36.0], Some("N")).unwrap();
+    assert!((lat - 47.61).abs() < 1e-9);
+    let lon = gps_to_decimal(&[122.0, 19.0, 48.0], Some("W")).unwrap();
+    assert!((lon + 122.33).abs() < 1e-9);
+    assert_eq!(gps_to_decimal(&[], None), None);
+
+    let num: Vec<u8> = [47u32, 36, 3660].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let den: Vec<u8> = [1u32, 1, 100].iter().flat_map(|v| v.to_le_bytes()).collect();
+    assert_eq!(gps_rationals(&num, &den), vec![47.0, 36.0, 36.6]);
+}
+
+#[test]
+fn parse_storage_provider_id_test() {
+    assert_eq!(
+        parse_storage_provider_id("OneDrive!S-1-5-21-1-2-3-1001!Business1|{A2C3E4F5-0000-0000-0000-000000000000}"),
+        Some(StorageProvider {
+            provider: "OneDrive",
+            sid: Some("S-1-5-21-1-2-3-1001"),
+            account: Some("Business1"),
+        })
+    );
+    assert_eq!(
+        parse_storage_provider_id("Dropbox"),
+        Some(StorageProvider {
+            provider: "Dropbox",
+            sid: None,
+            account: None,
+        })
+    );
+    assert_eq!(parse_storage_provider_id(""), None);
+    assert_eq!(pin_state(0x00080020), "AlwaysAvailable");
+    assert_eq!(pin_state(0x00500000), "OnlineOnly");
+    assert_eq!(sfgao_flags_to_string(0x60000000), "SFGAO_FOLDER | SFGAO_FILESYSTEM");
+    assert_eq!(sync_transfer_status_to_string(0x12), "STS_NEEDSDOWNLOAD | STS_HASERROR");
+    assert_eq!(sync_transfer_status_to_string(0), "STS_NONE");
+    assert_eq!(placeholder_status_to_string(0x10000009), "PS_MARKED_FOR_OFFLINE_AVAILABILITY | PS_CLOUDFILE_PLACEHOLDER | 0x10000000");
+    assert_eq!(file_attributes_flags_to_string(0x10), "FILE_ATTRIBUTE_DIRECTORY");
+    assert_eq!(file_attributes_flags_to_string(0x2006), "FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM | FILE_ATTRIBUTE_NOT_CONTENT_INDEXED");
+}
+
+#[test]
+fn parse_activity_app_id_test() {
+    let app_id = r#"[{"application":"308046B0AF4A39CB","platform":"windows_win32"},{"application":"{6D809377-6AF0-444B-8957-A3773F02200E}\\Notepad++\\notepad++.exe","platform":"x_exe_path"},{"application":"","platform":"packageId"}]"#;
+    let apps = parse_activity_app_id(app_id);
+    assert_eq!(apps.len(), 2);
+    let primary = primary_activity_app(&apps).unwrap();
+    assert_eq!(primary.platform, "x_exe_path");
+    assert_eq!(
+        resolve_known_folder(&primary.application),
+        "C:\\Program Files\\Notepad++\\notepad++.exe"
+    );
+    assert_eq!(resolve_known_folder("notepad.exe"), "notepad.exe");
+    assert_eq!(primary_activity_app(&[]), None);
+}
+
+#[test]
+fn activity_app_ids_test() {
+    let app_id = r#"[{"application":"Microsoft.Windows.Photos_8wekyb3d8bbwe!App","platform":"windows_universal"},{"application":"Microsoft.Windows.Photos_8wekyb3d8bbwe!app","platform":"packageId"},{"application":"","platform":"alternateId"}]"#;
+    assert_eq!(activity_app_ids(app_id), vec!["Microsoft.Windows.Photos_8wekyb3d8bbwe!App"]);
+    assert_eq!(activity_app_ids("notepad.exe"), vec!["notepad.exe"]);
+    assert!(activity_app_ids("").is_empty());
+}
+
+#[test]
+fn format_guid_test() {
+    let guid = [
+        0x77, 0x93, 0x80, 0x6D, 0xF0, 0x6A, 0x4B, 0x44, 0x89, 0x57, 0xA3, 0x77, 0x3F, 0x02, 0x20, 0x0E,
+    ];
+    assert_eq!(format_guid(&guid).unwrap(), "{6D809377-6AF0-444B-8957-A3773F02200E}");
+    assert_eq!(format_guid(&guid[1..]), None);
+}
+
+#[test]
+fn item_path_test() {
+    let path = "C:\\Users\\testuser\\Desktop\\Test-Word.docx";
+    assert_eq!(split_item_path(path), ("C:\\Users\\testuser\\Desktop", "Test-Word.docx"));
+    assert_eq!(file_extension("Test-Word.docx"), Some(".docx"));
+    assert_eq!(file_extension(".gitignore"), None);
+    assert_eq!(user_profile_from_path(path), Some("testuser"));
+    assert_eq!(user_profile_from_path("c:\\users\\Public\\a.txt"), Some("Public"));
+    assert_eq!(user_profile_from_path("C:\\Windows\\notepad.exe"), None);
+    assert_eq!(
+        file_attributes_to_string(&vec![0x20, 0x00, 0x00, 0x00]),
+        "FILE_ATTRIBUTE_ARCHIVE"
+    );
+}
+
+#[test]
+fn find_uri_param_test() {
+    let uri = "file:///C:/Users/testuser/Desktop/Test-Word.docx?VolumeId={AC048C6D-1E3C-4B21-B20D-75745DD788B3}&ObjectId={5E5EFB20-A904-11ED-A0EA-DC215CBBECEC}&KnownFolderId=ThisPCDesktopFolder&KnownFolderLength=25";
+    assert_eq!(find_uri_param(uri, "KnownFolderId"), Some("ThisPCDesktopFolder"));
+    assert_eq!(find_uri_param(uri, "KnownFolderLength"), Some("25"));
+    assert_eq!(find_uri_param(uri, "Missing"), None);
+    assert_eq!(find_uri_param("file:///C:/a.txt", "VolumeId"), None);
+}
+
 pub struct DropMe<F>
 where
     F: Fn(),